@@ -1,33 +1,134 @@
 use crate::prelude::*;
+use heed::{Database as HeedDatabase, Env, EnvOpenOptions, types::*, zerocopy::U64, byteorder::BE};
+
+type BEU64 = U64<BE>;
 
 lazy_static::lazy_static! {
-    pub static ref DB: Db = Db::open();
+    pub static ref DB: Db = Db::open("census-db").expect("Failed to open census database");
 }
 
-#[derive(Default)]
+/// Peers that submitted a record this recently are given to others (in seconds)
+const LIVE_PEER_TTL: u64 = 3600;
+/// Records sent by a peer this soon after its previous one are ignored (in seconds)
+const MIN_RECORD_INTERVAL: u64 = 3*60;
+
+#[derive(Debug)]
+pub enum DbError {
+    Io(std::io::Error),
+    Heed(HeedError),
+}
+
+impl From<std::io::Error> for DbError {
+    fn from(e: std::io::Error) -> Self {
+        DbError::Io(e)
+    }
+}
+
+impl From<HeedError> for DbError {
+    fn from(e: HeedError) -> Self {
+        DbError::Heed(e)
+    }
+}
+
+/// Error returned when a record cannot be stored.
+#[derive(Debug)]
+pub enum InsertRecordError {
+    Heed(HeedError),
+    /// The record is a duplicate or older than the latest one we have for the peer.
+    OutOfOrder { last_seq: u64 },
+    /// The peer already sent records with a sequence number, so legacy records could only be replays.
+    Downgrade,
+}
+
+impl From<HeedError> for InsertRecordError {
+    fn from(e: HeedError) -> Self {
+        InsertRecordError::Heed(e)
+    }
+}
+
+/// Records are kept in an LMDB database, so that history survives restarts.
+/// Operations are quick enough to be run directly from async code.
 pub struct Db {
     ips: RwLock<HashSet<String>>,
-    pub records: RwLock<Vec<DbRecord>>,
-    pub drain_history: RwLock<Vec<u64>>,
+    env: Env,
+    /// Every record received, grouped by the second they were received in.
+    /// Keys are big-endian timestamps so that records are ordered by time.
+    history: HeedDatabase<OwnedType<BEU64>, SerdeJson<Vec<Record>>>,
+    /// The latest record of each peer, keyed by peer id
+    latest: HeedDatabase<Str, SerdeJson<DbRecord>>,
     pub stats: RwLock<GetStatsResp>,
 }
 
 impl Db {
-    pub fn open() -> Db {
-        let drain_history_data = std::fs::read_to_string("drain_history.txt").unwrap_or_default();
-        let drain_history = drain_history_data
-            .split('\n')
-            .filter(|line| !line.is_empty())
-            .map(|line| line.parse::<u64>().expect("Invalid value in drain_history.txt"))
-            .collect::<Vec<_>>();
-
-        Db {
-            drain_history: RwLock::new(drain_history),
-            ..Default::default()
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Db, DbError> {
+        std::fs::create_dir_all(&path)?;
+        let env = EnvOpenOptions::new()
+            .map_size(16 * 1024 * 1024 * 1024) // 16GiB
+            .max_dbs(2)
+            .open(path)?;
+        let mut wtxn = env.write_txn()?;
+        let history = env.create_database(&mut wtxn, Some("history"))?;
+        let latest = env.create_database(&mut wtxn, Some("latest"))?;
+        wtxn.commit()?;
+
+        let db = Db {
+            ips: RwLock::new(HashSet::new()),
+            env,
+            history,
+            latest,
+            stats: RwLock::new(GetStatsResp::default()),
+        };
+        db.import_legacy_dumps()?;
+
+        Ok(db)
+    }
+
+    /// Imports the `data_N.json` files written by previous versions, that kept records in memory.
+    /// `drain_history.txt` is renamed once done so that this only happens once.
+    fn import_legacy_dumps(&self) -> Result<(), DbError> {
+        let Ok(drain_history) = std::fs::read_to_string("drain_history.txt") else { return Ok(()) };
+        let drain_count = drain_history.lines().filter(|line| !line.is_empty()).count();
+
+        let mut wtxn = self.env.write_txn()?;
+        for i in 0..drain_count {
+            let records = match std::fs::read_to_string(format!("data_{i}.json")).map(|json| serde_json::from_str::<Vec<DbRecord>>(&json)) {
+                Ok(Ok(records)) => records,
+                Ok(Err(e)) => {
+                    eprintln!("Failed to deserialize records from data_{i}.json: {e}");
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("Failed to read data_{i}.json: {e}");
+                    continue;
+                }
+            };
+            for record in records {
+                self.put_record(&mut wtxn, record)?;
+            }
+        }
+        wtxn.commit()?;
+
+        std::fs::rename("drain_history.txt", "drain_history.txt.imported")?;
+        println!("Imported {drain_count} legacy data files");
+        Ok(())
+    }
+
+    fn put_record(&self, wtxn: &mut heed::RwTxn, record: DbRecord) -> Result<(), HeedError> {
+        let key = BEU64::new(record.ts);
+        let mut records = self.history.get(&*wtxn, &key)?.unwrap_or_default();
+        records.push(record.r.clone());
+        self.history.put(wtxn, &key, &records)?;
+
+        let is_latest = self.latest.get(&*wtxn, &record.r.peer_id)?.map(|r| r.ts <= record.ts).unwrap_or(true);
+        if is_latest {
+            self.latest.put(wtxn, &record.r.peer_id, &record)?;
         }
+        Ok(())
     }
 
-    pub async fn insert_record(&self, record: Record, ip: String) {
+    /// Stores a record whose signature and freshness were already checked.
+    /// Records are refused if they don't come after the latest record of the peer.
+    pub async fn insert_record(&self, record: Record, ip: String) -> Result<(), InsertRecordError> {
         let mut ips = self.ips.write().await;
         let ip_tainted = !ips.insert(ip.clone());
         drop(ips);
@@ -35,37 +136,69 @@ impl Db {
         #[cfg(feature = "ip_filter")]
         if ip_tainted {
             eprintln!("Ignoring spam from {ip}");
-            return;
+            return Ok(());
+        }
+
+        let r = self.insert_record_inner(record, now_ts());
+        if let Err(InsertRecordError::Heed(e)) = &r {
+            eprintln!("Failed to store record from {ip}: {e}");
         }
+        r
+    }
 
-        let mut records = self.records.write().await;
+    fn insert_record_inner(&self, record: Record, ts: u64) -> Result<(), InsertRecordError> {
+        let mut wtxn = self.env.write_txn()?;
 
-        let last_ts = records.iter().rfind(|r| r.r.peer_id == record.peer_id).map(|r| r.ts);
+        let last_record = self.latest.get(&wtxn, &record.peer_id)?;
+        if let Some(last_record) = &last_record {
+            if record.is_legacy() && !last_record.r.is_legacy() {
+                return Err(InsertRecordError::Downgrade);
+            }
+            if !record.is_legacy() && record.seq <= last_record.r.seq {
+                return Err(InsertRecordError::OutOfOrder { last_seq: last_record.r.seq });
+            }
+        }
+
+        let last_ts = last_record.map(|r| r.ts);
         if let Some(last_ts) = last_ts {
-            if last_ts > now_ts() - 3*60 {
-                eprintln!("Ignoring record from {} (already have a record from {} seconds ago)", record.peer_id, now_ts() - last_ts);
-                return;
+            if last_ts > ts - MIN_RECORD_INTERVAL {
+                eprintln!("Ignoring record from {} (already have a record from {} seconds ago)", record.peer_id, ts - last_ts);
+                return Ok(());
             }
         }
 
-        if records.len() >= 55000 {
-            eprintln!("Database is full, ignoring record from {ip}");
-            return;
+        self.put_record(&mut wtxn, DbRecord { r: record, ts })?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Returns records received between `start` (inclusive) and `end` (exclusive), ordered by time.
+    pub fn records_between(&self, start: u64, end: u64) -> Result<Vec<DbRecord>, HeedError> {
+        let rotxn = self.env.read_txn()?;
+        let mut result = Vec::new();
+        for entry in self.history.range(&rotxn, &(BEU64::new(start)..BEU64::new(end)))? {
+            let (ts, records) = entry?;
+            result.extend(records.into_iter().map(|r| DbRecord { r, ts: ts.get() }));
         }
-        
-        records.push(DbRecord {
-            r: record,
-            ts: now_ts(),
-        });
+        Ok(result)
     }
 
     pub async fn draw_peers(&self, count: usize, exclude: &[String]) -> Vec<(String, Vec<String>)> {
-        self.records.read().await
-            .iter()
-            .filter(|r| !exclude.contains(&r.r.peer_id))
+        let live_since = now_ts() - LIVE_PEER_TTL;
+        let rotxn = match self.env.read_txn() {
+            Ok(rotxn) => rotxn,
+            Err(e) => {
+                eprintln!("Failed to read peers: {e}");
+                return Vec::new();
+            }
+        };
+        let Ok(latest) = self.latest.iter(&rotxn) else { return Vec::new() };
+        latest
+            .filter_map(|entry| entry.ok())
+            .filter(|(peer_id, r)| r.ts > live_since && !exclude.iter().any(|e| e.as_str() == *peer_id))
             .choose_multiple(&mut rand::thread_rng(), count)
             .into_iter()
-            .map(|r| (r.r.peer_id.clone(), r.r.addrs.clone()))
+            .map(|(_, r)| (r.r.peer_id, r.r.addrs))
             .collect::<Vec<_>>()
     }
 
@@ -75,79 +208,62 @@ impl Db {
 
     pub async fn shutdowner(&self) {
         use tokio::signal::{ctrl_c, unix::{SignalKind, signal}};
-        
+
         let crtl_c_fut = ctrl_c();
         let mut sig_term_receiver = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
         let sig_term_fut = sig_term_receiver.recv();
         select(Box::pin(crtl_c_fut), Box::pin(sig_term_fut)).await;
-        self.drain(None).await;
-        println!("Database ready to shut down");
-    }
-    
-    async fn save_drain_history(&self) {
-        let drain_history = self.drain_history.read().await;
-        let drain_history_data = drain_history.iter().map(|ts| ts.to_string()).collect::<Vec<_>>().join("\n");
-        drop(drain_history);
-        match tokio::fs::write("drain_history.txt", drain_history_data).await {
-            Ok(()) => (),
-            Err(e) => eprintln!("Failed to write drain history: {e}"),
-        }
-    }
-
-    async fn drain(&self, to_drain: Option<usize>) {
-        let mut records = self.records.write().await;
-        let to_drain = to_drain.unwrap_or(records.len());
-        let drained = records.drain(..to_drain).collect::<Vec<_>>();
-        if drained.is_empty() {
-            return;
-        }
-        drop(records);
-
-        let mut drain_history = self.drain_history.write().await;
-        let drain_index = drain_history.len();
-        drain_history.push(now_ts());
-        drop(drain_history);
-        
-        let drained_json = match serde_json::to_string(&drained) {
-            Ok(json) => json,
-            Err(e) => {
-                eprintln!("Failed to serialize records: {e}");
-                return;
-            }
-        };
-        let filename = format!("data_{drain_index}.json");
-        let r = tokio::fs::write(&filename, drained_json).await;
-        if let Err(e) = r {
-            eprintln!("Failed to write records to {filename}: {e}");
+        if let Err(e) = self.env.force_sync() {
+            eprintln!("Failed to sync database: {e}");
         }
-        self.save_drain_history().await;
+        println!("Database ready to shut down");
     }
 
     pub async fn update_stats_task(&self) {
         loop {
-            sleep(Duration::from_secs(5*60)).await;
             self.compute_stats().await;
+            sleep(Duration::from_secs(5*60)).await;
         }
     }
 
-    pub async fn drain_task(&self) {
-        let mut i = 0;
+    /// Periodically forgets which IPs sent records, so that peers behind them can submit again.
+    pub async fn clear_ips_task(&self) {
         loop {
-            i += 1;
-            sleep(Duration::from_secs(60)).await;
-            
-            if i % 20 == 0 {
-                self.ips.write().await.clear()
-            }
+            sleep(Duration::from_secs(20*60)).await;
+            self.ips.write().await.clear()
+        }
+    }
+}
 
-            let records = self.records.read().await;
-            if records.len() <= 1000 {
-                continue;
-            }
-            let to_drain = records.len() - 1000;
-            drop(records);
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::record::tests::record;
 
-            self.drain(Some(to_drain)).await;
-        }
+    pub(crate) fn open_temp_db() -> (Db, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("admarus-census-test-{}", rand::random::<u64>()));
+        (Db::open(&path).unwrap(), path)
+    }
+
+    #[test]
+    fn test_replays() {
+        let (db, path) = open_temp_db();
+        let now = now_ts();
+
+        db.insert_record_inner(record("peer", now, 2), now).unwrap();
+
+        // Same record replayed, or an older one
+        let r = db.insert_record_inner(record("peer", now, 2), now);
+        assert!(matches!(r, Err(InsertRecordError::OutOfOrder { last_seq: 2 })));
+        let r = db.insert_record_inner(record("peer", now, 1), now);
+        assert!(matches!(r, Err(InsertRecordError::OutOfOrder { last_seq: 2 })));
+
+        // Legacy records can't replace newer ones
+        let r = db.insert_record_inner(record("peer", 0, 0), now);
+        assert!(matches!(r, Err(InsertRecordError::Downgrade)));
+
+        assert_eq!(db.records_between(0, now + 1).unwrap().len(), 1);
+
+        std::fs::remove_dir_all(path).unwrap();
     }
 }
@@ -23,9 +23,19 @@ async fn submit_record(record: web::Json<ApiRecord>, req: HttpRequest) -> impl R
     if record.peer_id != peer_id.to_string() {
         return HttpResponse::Unauthorized().body(format!("Peer ID mismatch (record: {}, public key: {})", record.peer_id, peer_id));
     }
-    let record_hash = record.hash();
-    if !public_key.verify(&record_hash, signature.as_slice()) {
-        return HttpResponse::Unauthorized().body(format!("Invalid signature (record hash: {record_hash:x})"));
+    if !record.verify_signature(&public_key, signature.as_slice()) {
+        return HttpResponse::Unauthorized().body("Invalid signature");
+    }
+
+    // Check freshness
+    #[cfg(not(feature = "legacy_records"))]
+    if record.is_legacy() {
+        return HttpResponse::BadRequest().body("Records without timestamp and sequence number are no longer accepted");
+    }
+    match record.check_freshness(now_ts()) {
+        Ok(()) => (),
+        Err(FreshnessError::Stale { age }) => return HttpResponse::BadRequest().body(format!("Stale record (issued {age} seconds ago)")),
+        Err(FreshnessError::FromFuture) => return HttpResponse::BadRequest().body("Record issued in the future"),
     }
 
     // Check validity
@@ -51,8 +61,12 @@ async fn submit_record(record: web::Json<ApiRecord>, req: HttpRequest) -> impl R
     record.folders = valid_folders;
 
     let ip = req.headers().get("X-Real-IP").and_then(|ip| ip.to_str().ok()).unwrap_or("unknown").to_string();
-    DB.insert_record(record, ip).await;
-    HttpResponse::Ok().body("Success!")
+    match DB.insert_record(record, ip).await {
+        Ok(()) => HttpResponse::Ok().body("Success!"),
+        Err(InsertRecordError::OutOfOrder { last_seq }) => HttpResponse::Conflict().body(format!("Sequence number must be greater than {last_seq}")),
+        Err(InsertRecordError::Downgrade) => HttpResponse::Conflict().body("Legacy records are no longer accepted from this peer"),
+        Err(InsertRecordError::Heed(_)) => HttpResponse::InternalServerError().body("Failed to store record"),
+    }
 }
 
 #[derive(Deserialize)]
@@ -76,3 +90,30 @@ async fn get_stats() -> impl Responder {
     let stats = DB.get_stats().await;
     HttpResponse::Ok().json(stats)
 }
+
+/// Longest period stats can be computed over (in seconds)
+const MAX_STATS_RANGE: u64 = 31*86400;
+
+#[derive(Deserialize)]
+struct GetStatsRangeQuery {
+    /// Unix timestamp, defaults to 24 hours before `end`
+    start: Option<u64>,
+    /// Unix timestamp (exclusive), defaults to now
+    end: Option<u64>,
+}
+
+#[get("/api/v0/stats/range")]
+async fn get_stats_range(query: web::Query<GetStatsRangeQuery>) -> impl Responder {
+    let end = query.end.unwrap_or_else(|| now_ts() + 1);
+    let start = query.start.unwrap_or(end.saturating_sub(86400));
+    if start >= end {
+        return HttpResponse::BadRequest().body("Start must be before end");
+    }
+    if end - start > MAX_STATS_RANGE {
+        return HttpResponse::BadRequest().body(format!("Range too long (max {MAX_STATS_RANGE} seconds)"));
+    }
+    match DB.stats_between(start, end) {
+        Ok(stats) => HttpResponse::Ok().json(stats),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Failed to compute stats: {e}")),
+    }
+}
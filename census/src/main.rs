@@ -9,13 +9,14 @@ pub use prelude::*;
 #[actix_web::main]
 async fn main() {
     let f1 = DB.shutdowner();
-    let f2 = DB.drain_task();
+    let f2 = DB.clear_ips_task();
     let f3 = DB.update_stats_task();
     let f4 = HttpServer::new(|| {
         App::new()
             .service(submit_record)
             .service(get_peers)
             .service(get_stats)
+            .service(get_stats_range)
         })
         .bind(("127.0.0.1", 14364)).expect("Can not bind to address")
         .run();
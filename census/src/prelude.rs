@@ -7,5 +7,6 @@ pub(crate) use rand::seq::IteratorRandom;
 pub(crate) use sha2_derive::Hashable;
 pub(crate) use libp2p_identity::PublicKey;
 pub(crate) use futures::future::select;
+pub(crate) use heed::Error as HeedError;
 
 pub(crate) fn now_ts() -> u64 { SystemTime::now().duration_since(UNIX_EPOCH).expect("System time incorrect").as_secs() }
@@ -1,5 +1,10 @@
 use crate::prelude::*;
 
+/// Records signed longer ago than this are refused (in seconds)
+const MAX_RECORD_AGE: u64 = 10*60;
+/// Records signed this far in the future are accepted, to tolerate clock drift (in seconds)
+const MAX_CLOCK_DRIFT: u64 = 5*60;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Hashable)]
 pub struct Record {
     pub peer_id: String,
@@ -8,6 +13,67 @@ pub struct Record {
     /// Since we can't handle the list of files (think about how wikipedia alone has 6M+ pages), this contains folders.
     /// Each folder has a CID, and the count of files it indexes in that folder (non-recursive).
     pub folders: Vec<(String, u64)>,
+    /// Unix timestamp of when the record was signed.
+    /// Missing (zero) in legacy records.
+    #[serde(default)]
+    pub issued_at: u64,
+    /// Increases with each record submitted by the peer, so that records can't be replayed.
+    /// Missing (zero) in legacy records.
+    #[serde(default)]
+    pub seq: u64,
+}
+
+/// Records sent by nodes that predate [Record::issued_at] and [Record::seq].
+/// They were signed without those fields.
+#[derive(Hashable)]
+struct LegacyRecord {
+    peer_id: String,
+    addrs: Vec<String>,
+    folders: Vec<(String, u64)>,
+}
+
+impl Record {
+    pub fn is_legacy(&self) -> bool {
+        self.issued_at == 0 && self.seq == 0
+    }
+
+    /// Checks the record was signed recently.
+    /// Legacy records have no timestamp and always pass.
+    pub fn check_freshness(&self, now: u64) -> Result<(), FreshnessError> {
+        if self.is_legacy() {
+            return Ok(());
+        }
+        if self.issued_at.saturating_add(MAX_RECORD_AGE) < now {
+            return Err(FreshnessError::Stale { age: now - self.issued_at });
+        }
+        if self.issued_at > now + MAX_CLOCK_DRIFT {
+            return Err(FreshnessError::FromFuture);
+        }
+        Ok(())
+    }
+
+    /// Checks the record was signed by the owner of `public_key`.
+    /// Legacy records are hashed without the fields they lack, so new records can't be downgraded to legacy ones without breaking their signature.
+    pub fn verify_signature(&self, public_key: &PublicKey, signature: &[u8]) -> bool {
+        match self.is_legacy() {
+            true => {
+                let legacy_record = LegacyRecord {
+                    peer_id: self.peer_id.clone(),
+                    addrs: self.addrs.clone(),
+                    folders: self.folders.clone(),
+                };
+                public_key.verify(&legacy_record.hash(), signature)
+            },
+            false => public_key.verify(&self.hash(), signature),
+        }
+    }
+}
+
+/// Error returned when a record wasn't signed recently.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FreshnessError {
+    Stale { age: u64 },
+    FromFuture,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,3 +85,27 @@ pub struct DbRecord {
 impl AsRef<Record> for DbRecord {
     fn as_ref(&self) -> &Record { &self.r }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn record(peer_id: &str, issued_at: u64, seq: u64) -> Record {
+        Record {
+            peer_id: peer_id.to_string(),
+            addrs: Vec::new(),
+            folders: Vec::new(),
+            issued_at,
+            seq,
+        }
+    }
+
+    #[test]
+    fn test_freshness() {
+        let now = now_ts();
+        assert_eq!(record("peer", now - 60, 1).check_freshness(now), Ok(()));
+        assert_eq!(record("peer", now - 3600, 1).check_freshness(now), Err(FreshnessError::Stale { age: 3600 }));
+        assert_eq!(record("peer", now + 3600, 1).check_freshness(now), Err(FreshnessError::FromFuture));
+        assert_eq!(record("peer", 0, 0).check_freshness(now), Ok(()));
+    }
+}
@@ -1,5 +1,3 @@
-#![allow(clippy::too_many_arguments)]
-
 use crate::prelude::*;
 
 #[derive(Clone, Default, PartialEq, Debug, Serialize)]
@@ -33,26 +31,6 @@ fn count_record(record: Record, peers: &mut HashSet<String>, folders: &mut HashM
     peer_documents.insert(record.peer_id, file_count);
 }
 
-fn count_records(
-    records: Vec<DbRecord>, now: u64,
-    peers_24h: &mut HashSet<String>, peers_1h: &mut HashSet<String>, prev_peers_24h: &mut HashSet<String>, prev_peers_1h: &mut HashSet<String>,
-    folders_24h: &mut HashMap<String, u64>, folders_1h: &mut HashMap<String, u64>, prev_folders_24h: &mut HashMap<String, u64>, prev_folders_1h: &mut HashMap<String, u64>,
-    peer_documents_24h: &mut HashMap<String, u64>, peer_documents_1h: &mut HashMap<String, u64>, prev_peer_documents_24h: &mut HashMap<String, u64>, prev_peer_documents_1h: &mut HashMap<String, u64>,
-) {
-    for DbRecord { r, ts } in records {
-        if ts > now - 86400 {
-            if ts > now - 3600 {
-                count_record(r.clone(), peers_1h, folders_1h, peer_documents_1h)
-            } else if ts > now - 2*3600 {
-                count_record(r.clone(), prev_peers_1h, prev_folders_1h, prev_peer_documents_1h)
-            }
-            count_record(r, peers_24h, folders_24h, peer_documents_24h)
-        } else if ts > now - 2*86400 {
-            count_record(r, prev_peers_24h, prev_folders_24h, prev_peer_documents_24h)
-        }
-    }
-}
-
 fn into_stats(peers: HashSet<String>, folders: HashMap<String, u64>, peer_documents: HashMap<String, u64>) -> NetworkStats {
     NetworkStats {
         peers: peers.len() as u64,
@@ -70,79 +48,44 @@ fn into_stats(peers: HashSet<String>, folders: HashMap<String, u64>, peer_docume
 }
 
 impl Db {
-    pub async fn compute_stats(&self) {
-        let mut peers_24h = HashSet::new();
-        let mut folders_24h = HashMap::new();
-        let mut peer_documents_24h = HashMap::new();
-
-        let mut prev_peers_24h = HashSet::new();
-        let mut prev_folders_24h = HashMap::new();
-        let mut prev_peer_documents_24h = HashMap::new();
-
-        let mut peers_1h = HashSet::new();
-        let mut folders_1h = HashMap::new();
-        let mut peer_documents_1h = HashMap::new();
-
-        let mut prev_peers_1h = HashSet::new();
-        let mut prev_folders_1h = HashMap::new();
-        let mut prev_peer_documents_1h = HashMap::new();
-
-        // Start by using the data that's in Db before it expires
-        let now = now_ts();
-        let current_records = self.records.read().await.clone();
-        count_records(
-            current_records, now, &mut peers_24h, &mut peers_1h, &mut prev_peers_24h, &mut prev_peers_1h,
-            &mut folders_24h, &mut folders_1h, &mut prev_folders_24h, &mut prev_folders_1h,
-            &mut peer_documents_24h, &mut peer_documents_1h, &mut prev_peer_documents_24h, &mut prev_peer_documents_1h
-        );
-
-        // Finish by reading the data that's on disk
-        let first_drain_24h = self.drain_history.read().await.iter().position(|ts| *ts > now - 2*86400);
-        let first_drain = first_drain_24h.map(|i| i - 1);
-        let drain_len = self.drain_history.read().await.len();
-        if let Some(first_drain) = first_drain {
-            for i in first_drain..drain_len {
-                let records = match tokio::fs::read_to_string(format!("data_{i}.json")).await {
-                    Ok(json) => match serde_json::from_str::<Vec<DbRecord>>(&json) {
-                        Ok(records) => records,
-                        Err(e) => {
-                            eprintln!("Failed to deserialize records from data_{i}.json: {e}");
-                            continue;
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("Failed to read data_{i}.json: {e}");
-                        continue;
-                    }
-                };
-                count_records(
-                    records, now, &mut peers_24h, &mut peers_1h, &mut prev_peers_24h, &mut prev_peers_1h,
-                    &mut folders_24h, &mut folders_1h, &mut prev_folders_24h, &mut prev_folders_1h,
-                    &mut peer_documents_24h, &mut peer_documents_1h, &mut prev_peer_documents_24h, &mut prev_peer_documents_1h
-                );
-            }
+    /// Computes stats over records received between `start` (inclusive) and `end` (exclusive).
+    pub fn stats_between(&self, start: u64, end: u64) -> Result<NetworkStats, HeedError> {
+        let mut peers = HashSet::new();
+        let mut folders = HashMap::new();
+        let mut peer_documents = HashMap::new();
+        for DbRecord { r, .. } in self.records_between(start, end)? {
+            count_record(r, &mut peers, &mut folders, &mut peer_documents);
         }
+        Ok(into_stats(peers, folders, peer_documents))
+    }
 
-        // Collect the stats
-        let stats = GetStatsResp {
-            stats_1h: into_stats(peers_1h, folders_1h, peer_documents_1h),
-            prev_stats_1h: into_stats(prev_peers_1h, prev_folders_1h, prev_peer_documents_1h),
-            stats_24h: into_stats(peers_24h, folders_24h, peer_documents_24h),
-            prev_stats_24h: into_stats(prev_peers_24h, prev_folders_24h, prev_peer_documents_24h),
-        };
+    /// Computes stats over the last hour and day, and the ones before them.
+    fn recent_stats(&self) -> Result<GetStatsResp, HeedError> {
+        let end = now_ts() + 1;
+        Ok(GetStatsResp {
+            stats_1h: self.stats_between(end - 3600, end)?,
+            prev_stats_1h: self.stats_between(end - 2*3600, end - 3600)?,
+            stats_24h: self.stats_between(end - 86400, end)?,
+            prev_stats_24h: self.stats_between(end - 2*86400, end - 86400)?,
+        })
+    }
 
-        // Update the stats
-        *self.stats.write().await = stats;
+    pub async fn compute_stats(&self) {
+        match self.recent_stats() {
+            Ok(stats) => *self.stats.write().await = stats,
+            Err(e) => eprintln!("Failed to compute stats: {e}"),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::tests::open_temp_db;
 
     #[tokio::test]
     async fn test_stats() {
-        let db = Db::default();
+        let (db, path) = open_temp_db();
         for i in 0..100 {
             let record = Record {
                 peer_id: format!("peer{i}"),
@@ -150,9 +93,11 @@ mod tests {
                 folders: vec![
                     (format!("unique{i}"), 10),
                     (format!("common{}", i%10), 1)
-                ]
+                ],
+                issued_at: now_ts(),
+                seq: 1,
             };
-            db.insert_record(record, format!("ip{i}")).await;
+            db.insert_record(record, format!("ip{i}")).await.unwrap();
         }
         db.compute_stats().await;
         
@@ -165,6 +110,12 @@ mod tests {
             documents: 1100, // 10 + 1 for each 100 peers
             different_documents: 1010, // 10 for each 100 unique folders + 1 for each 10 common folders
             median_documents_per_peer: 11,
-        })
+        });
+
+        let now = now_ts();
+        assert_eq!(db.records_between(now - 60, now + 1).unwrap().len(), 100);
+        assert_eq!(db.stats_between(0, now - 60).unwrap(), Default::default());
+
+        std::fs::remove_dir_all(path).unwrap();
     }
 }
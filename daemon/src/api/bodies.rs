@@ -4,6 +4,29 @@ use super::*;
 pub struct ApiSearchQuery {
     /// Raw search query
     pub q: String,
+    /// Which results to look for first, defaults to [ApiSearchPriority::Variable]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<ApiSearchPriority>,
+    /// Number of documents to find before switching from speed to relevance, with [ApiSearchPriority::Variable]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until_documents: Option<usize>,
+    /// Maximum number of concurrent requests to send to peers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub req_limit: Option<usize>,
+    /// Number of milliseconds to wait for a peer to respond
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiSearchPriority {
+    /// Get results fast without taking relevance into account
+    Speed,
+    /// Focus on finding the best results first
+    Relevance,
+    /// Speed first, then relevance once [ApiSearchQuery::until_documents] documents have been found
+    Variable,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -20,6 +43,49 @@ pub struct ApiResultsQuery {
     pub id: u64,
 }
 
+/// Events sent on the search event stream, each as a server-sent event named after the variant
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ApiSearchEvent {
+    /// A validated result, along with the peer that provided it
+    Result {
+        result: DocumentResult,
+        provider: String,
+        /// Whether the daemon generated the result again from the document and got the same
+        #[serde(default)]
+        verified: bool,
+    },
+    /// Sent periodically while the search is ongoing
+    Progress {
+        queried_peers: usize,
+        final_peers: usize,
+        ongoing_queries: usize,
+    },
+    /// No more results will be sent
+    SearchOver,
+}
+
+impl ApiSearchEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ApiSearchEvent::Result { .. } => "result",
+            ApiSearchEvent::Progress { .. } => "progress",
+            ApiSearchEvent::SearchOver => "search_over",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ApiSearchPriorityQuery {
+    /// Unique search identifier from [ApiSearchResponse::id]
+    pub id: u64,
+    /// New priority of the search
+    pub priority: ApiSearchPriority,
+    /// Same as [ApiSearchQuery::until_documents]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until_documents: Option<usize>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct ApiResultQuery {
     /// Unique search identifier from [ApiSearchResponse::id]
@@ -28,6 +94,24 @@ pub struct ApiResultQuery {
     pub cid: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub struct ApiVoteQuery {
+    /// Unique search identifier from [ApiSearchResponse::id]
+    pub id: u64,
+    /// CID of the voted result
+    pub cid: String,
+    pub vote: ApiVote,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiVote {
+    /// The result is relevant, its providers are rewarded
+    Up,
+    /// The result is spam or irrelevant, its providers are penalized
+    Down,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct ApiVersionResponse {
     /// Version of the protocol
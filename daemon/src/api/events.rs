@@ -0,0 +1,31 @@
+use super::*;
+use tokio::sync::broadcast::error::RecvError;
+
+pub(super) async fn search_events((query, search_park): (ApiResultsQuery, Arc<SearchPark>)) -> Result<Box<dyn warp::Reply>, Infallible> {
+    let Some((past_events, receiver)) = search_park.subscribe(query.id as usize).await else {
+        return Ok(Box::new(Response::builder().status(400).body("Search not found".to_string()).unwrap()));
+    };
+
+    // Stops after the search is over
+    let over = matches!(past_events.last(), Some(ApiSearchEvent::SearchOver));
+    let upcoming_events = futures::stream::unfold((receiver, over), |(mut receiver, over)| async move {
+        if over {
+            return None;
+        }
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let over = matches!(event, ApiSearchEvent::SearchOver);
+                    return Some((event, (receiver, over)));
+                },
+                Err(RecvError::Lagged(count)) => warn!("Search event stream skipped {count} events"),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    let events = futures::stream::iter(past_events)
+        .chain(upcoming_events)
+        .map(|event| warp::sse::Event::default().event(event.name()).json_data(&event));
+    Ok(Box::new(warp::sse::reply(warp::sse::keep_alive().stream(events))))
+}
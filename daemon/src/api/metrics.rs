@@ -0,0 +1,6 @@
+use super::*;
+
+pub(super) async fn metrics((index, kamilata, search_park): (DocumentIndex, NodeController, Arc<SearchPark>)) -> Result<impl warp::Reply, Infallible> {
+    let metrics = render_metrics(&index, &kamilata, &search_park).await;
+    Ok(Response::builder().header("Content-Type", "application/openmetrics-text; version=1.0.0; charset=utf-8").body(metrics).unwrap())
+}
@@ -4,70 +4,277 @@ use warp::{Filter, http::Response};
 use std::{convert::Infallible, net::SocketAddr};
 
 mod bodies;
+mod events;
 mod indexing_status;
 mod local_search;
+mod metrics;
+mod peers;
 mod search;
 mod results;
+mod verification;
 mod version;
 use {
     bodies::*,
+    events::*,
     indexing_status::*,
     local_search::*,
+    metrics::*,
+    peers::*,
     search::*,
     results::*,
+    verification::*,
     version::*,
 };
 
+/// Searches are dropped when nobody fetched their results or listened to their events for this long
+const SEARCH_EXPIRATION: Duration = Duration::from_secs(7);
+const SEARCH_EVENTS_CAPACITY: usize = 256;
+
+enum SearchCommand {
+    SetPriority(SearchPriority),
+    /// Stops querying peers, results already received are still delivered
+    Cancel,
+}
+
 struct OngoingSearch {
     query: Arc<Query>,
-    results: Vec<(DocumentResult, PeerId)>,
+    /// Results waiting to be fetched, only used when nobody listens to events
+    results: Vec<(DocumentResult, PeerId, bool)>,
     providers: HashMap<String, HashSet<PeerId>>,
+    /// Reputation events already recorded for providers in this search
+    recorded: HashSet<(PeerId, ReputationEvent)>,
     last_fetch: Instant,
+    events: tokio::sync::broadcast::Sender<ApiSearchEvent>,
+    over: bool,
+    commands: tokio::sync::mpsc::UnboundedSender<SearchCommand>,
 }
 
 pub struct SearchPark {
     searches: RwLock<HashMap<usize, OngoingSearch>>,
+    sw: Arc<SwarmManager>,
+    config: Arc<Args>,
 }
 
 impl SearchPark {
-    pub fn new() -> SearchPark {
+    pub fn new(sw: Arc<SwarmManager>, config: Arc<Args>) -> SearchPark {
         SearchPark {
             searches: RwLock::new(HashMap::new()),
+            sw,
+            config,
         }
     }
 
     pub async fn insert(self: Arc<Self>, query: Query, controller: SearchController) -> usize {
         let id = rand::random();
+        let (events, _) = tokio::sync::broadcast::channel(SEARCH_EVENTS_CAPACITY);
+        let (commands, mut command_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let query = Arc::new(query);
         self.searches.write().await.insert(id, OngoingSearch {
-            query: Arc::new(query.clone()),
+            query: Arc::clone(&query),
             results: Vec::new(),
             providers: HashMap::new(),
-            last_fetch: Instant::now()
+            recorded: HashSet::new(),
+            last_fetch: Instant::now(),
+            events,
+            over: false,
+            commands,
         });
         tokio::spawn(async move {
             let mut controller = controller;
-            while let Some((result, peer_id)) = controller.recv().await {
-                let Ok(result) = result.validate(&query) else {continue};
-                let mut searches = self.searches.write().await;
-                let Some(search) = searches.get_mut(&id) else {break};
-                search.providers.entry(result.cid.clone()).or_insert_with(HashSet::new).insert(peer_id);
-                search.results.push((result, peer_id));
-                if search.last_fetch.elapsed() > Duration::from_secs(7) {
-                    searches.remove(&id);
-                    trace!("Search {id} expired");
-                    break;
+            let mut progress_interval = tokio::time::interval(Duration::from_secs(1));
+            let mut verifications = FuturesUnordered::new();
+            loop {
+                tokio::select! {
+                    result = controller.recv() => {
+                        let Some((result, peer_id)) = result else {break};
+                        if !self.on_result(id, &query, result, peer_id, &mut verifications).await {
+                            return;
+                        }
+                    },
+                    Some((result, peer_id, verification, dns_link)) = verifications.next(), if !verifications.is_empty() => {
+                        if !self.on_verification(id, result, peer_id, verification, dns_link).await {
+                            return;
+                        }
+                    },
+                    command = command_receiver.recv() => match command {
+                        Some(SearchCommand::SetPriority(priority)) => controller.set_priority(priority).await,
+                        Some(SearchCommand::Cancel) => {
+                            debug!("Search {id} canceled");
+                            let remaining = controller.finish().await;
+                            for (result, peer_id) in remaining.hits {
+                                if !self.on_result(id, &query, result, peer_id, &mut verifications).await {
+                                    return;
+                                }
+                            }
+                            break;
+                        },
+                        None => return,
+                    },
+                    _ = progress_interval.tick() => {
+                        let event = ApiSearchEvent::Progress {
+                            queried_peers: controller.queried_peers().await,
+                            final_peers: controller.final_peers().await,
+                            ongoing_queries: controller.ongoing_queries().await,
+                        };
+                        let mut searches = self.searches.write().await;
+                        let Some(search) = searches.get_mut(&id) else {return};
+                        if search.events.receiver_count() > 0 {
+                            let _ = search.events.send(event);
+                            search.last_fetch = Instant::now();
+                        } else if search.last_fetch.elapsed() > SEARCH_EXPIRATION {
+                            searches.remove(&id);
+                            trace!("Search {id} expired");
+                            return;
+                        }
+                    },
                 }
             }
+
+            // Results being verified are still part of the search
+            while let Some((result, peer_id, verification, dns_link)) = verifications.next().await {
+                if !self.on_verification(id, result, peer_id, verification, dns_link).await {
+                    return;
+                }
+            }
+
+            let mut searches = self.searches.write().await;
+            let Some(search) = searches.get_mut(&id) else {return};
+            search.over = true;
+            let _ = search.events.send(ApiSearchEvent::SearchOver);
         });
         id
     }
 
+    /// Records a result received from a peer.
+    /// Some results are verified before being delivered, depending on [Args::verification_rate].
+    /// Returns false if the search doesn't exist anymore.
+    async fn on_result(&self, id: usize, query: &Arc<Query>, result: DocumentResult, peer_id: PeerId, verifications: &mut FuturesUnordered<VerificationFuture>) -> bool {
+        let result = match result.validate(query) {
+            Ok(result) => {
+                self.record_once(id, peer_id, ReputationEvent::ReturnedResults).await;
+                result
+            },
+            Err(_) => {
+                self.sw.record(&peer_id, ReputationEvent::AutoDownvote).await;
+                return true;
+            },
+        };
+        if rand::random::<f64>() < self.config.verification_rate {
+            verifications.push(verify_result(result, peer_id, Arc::clone(query), Arc::clone(&self.config)));
+            return true;
+        }
+        self.deliver(id, result, peer_id, false).await
+    }
+
+    /// Returns false if the search doesn't exist anymore.
+    async fn on_verification(&self, id: usize, result: DocumentResult, peer_id: PeerId, verification: Verification, dns_link: DnsLink) -> bool {
+        match dns_link {
+            DnsLink::Correct => self.record_once(id, peer_id, ReputationEvent::CorrectDnsLink).await,
+            DnsLink::Incorrect => self.sw.record(&peer_id, ReputationEvent::IncorrectDnsLink).await,
+            DnsLink::Unchecked => (),
+        }
+        match verification {
+            Verification::Agrees => {
+                self.record_once(id, peer_id, ReputationEvent::AutoUpvote).await;
+                self.deliver(id, result, peer_id, true).await
+            },
+            Verification::Unavailable => self.deliver(id, result, peer_id, false).await,
+            Verification::Disagrees => {
+                warn!("{peer_id} lied about the content of {}", result.cid);
+                self.sw.ban(&peer_id).await;
+                true
+            },
+        }
+    }
+
+    /// Records an event at most once per search and provider, so that returning many results doesn't pay more than returning one.
+    async fn record_once(&self, id: usize, peer_id: PeerId, event: ReputationEvent) {
+        let mut searches = self.searches.write().await;
+        let Some(search) = searches.get_mut(&id) else {return};
+        let first_time = search.recorded.insert((peer_id, event));
+        drop(searches);
+        if first_time {
+            self.sw.record(&peer_id, event).await;
+        }
+    }
+
+    /// Makes a result available to API clients.
+    /// Returns false if the search doesn't exist anymore.
+    async fn deliver(&self, id: usize, result: DocumentResult, peer_id: PeerId, verified: bool) -> bool {
+        let mut searches = self.searches.write().await;
+        let Some(search) = searches.get_mut(&id) else {return false};
+        search.providers.entry(result.cid.clone()).or_insert_with(HashSet::new).insert(peer_id);
+        match search.events.receiver_count() {
+            0 => search.results.push((result, peer_id, verified)),
+            _ => {
+                let _ = search.events.send(ApiSearchEvent::Result { result, provider: peer_id.to_string(), verified });
+                search.last_fetch = Instant::now();
+            }
+        }
+        if search.last_fetch.elapsed() > SEARCH_EXPIRATION {
+            searches.remove(&id);
+            trace!("Search {id} expired");
+            return false;
+        }
+        true
+    }
+
+    /// Returns false if the search doesn't exist or is already over.
+    pub async fn set_priority(self: Arc<Self>, id: usize, priority: SearchPriority) -> bool {
+        let searches = self.searches.read().await;
+        let Some(search) = searches.get(&id) else {return false};
+        !search.over && search.commands.send(SearchCommand::SetPriority(priority)).is_ok()
+    }
+
+    /// Returns false if the search doesn't exist or is already over.
+    pub async fn cancel(self: Arc<Self>, id: usize) -> bool {
+        let searches = self.searches.read().await;
+        let Some(search) = searches.get(&id) else {return false};
+        !search.over && search.commands.send(SearchCommand::Cancel).is_ok()
+    }
+
+    /// Records the user's opinion of a result on the peers that provided it.
+    /// Returns false if the search doesn't exist or no peer provided that result.
+    pub async fn vote(self: Arc<Self>, id: usize, cid: &str, vote: ApiVote) -> bool {
+        let searches = self.searches.read().await;
+        let Some(providers) = searches.get(&id).and_then(|s| s.providers.get(cid)).cloned() else {return false};
+        drop(searches);
+        let event = match vote {
+            ApiVote::Up => ReputationEvent::UserUpvote,
+            ApiVote::Down => ReputationEvent::UserDownvote,
+        };
+        for peer_id in providers {
+            self.record_once(id, peer_id, event).await;
+        }
+        true
+    }
+
+    /// Returns the events that happened since the last fetch, and a receiver for the upcoming ones.
+    pub async fn subscribe(self: Arc<Self>, id: usize) -> Option<(Vec<ApiSearchEvent>, tokio::sync::broadcast::Receiver<ApiSearchEvent>)> {
+        let mut searches = self.searches.write().await;
+        let search = searches.get_mut(&id)?;
+        search.last_fetch = Instant::now();
+        let receiver = search.events.subscribe();
+        let mut events = std::mem::take(&mut search.results)
+            .into_iter()
+            .map(|(result, peer_id, verified)| ApiSearchEvent::Result { result, provider: peer_id.to_string(), verified })
+            .collect::<Vec<_>>();
+        if search.over {
+            events.push(ApiSearchEvent::SearchOver);
+        }
+        Some((events, receiver))
+    }
+
+    pub async fn active_searches(&self) -> usize {
+        self.searches.read().await.len()
+    }
+
     pub async fn get_query(self: Arc<Self>, id: usize) -> Option<Arc<Query>> {
         let searches = self.searches.read().await;
         searches.get(&id).map(|s| Arc::clone(&s.query))
     }
 
-    pub async fn fetch_results(self: Arc<Self>, id: usize) -> Option<Vec<(DocumentResult, PeerId)>> {
+    pub async fn fetch_results(self: Arc<Self>, id: usize) -> Option<Vec<(DocumentResult, PeerId, bool)>> {
         let mut searches = self.searches.write().await;
         let OngoingSearch { results, last_fetch, .. } =  searches.get_mut(&id)?;
         *last_fetch = Instant::now();
@@ -84,20 +291,50 @@ pub async fn serve_api(config: Arc<Args>, index: DocumentIndex, search_park: Arc
         .map(move || index2.clone())
         .and_then(indexing_status);
 
+    let index2 = index.clone();
     let local_search = warp::get()
         .and(warp::path("local-search"))
         .and(warp::query::<ApiSearchQuery>())
-        .map(move |q: ApiSearchQuery| (q, index.clone()))
+        .map(move |q: ApiSearchQuery| (q, index2.clone()))
         .and_then(local_search);
     
+    let index2 = index.clone();
     let search_park2 = Arc::clone(&search_park);
     let kamilata2 = kamilata.clone();
     let search = warp::get()
         .and(warp::path("search"))
         .and(warp::query::<ApiSearchQuery>())
-        .map(move |q: ApiSearchQuery| (q, Arc::clone(&search_park2), kamilata2.clone()))
+        .map(move |q: ApiSearchQuery| (q, Arc::clone(&search_park2), kamilata2.clone(), index2.clone()))
         .and_then(search);
 
+    let search_park2 = Arc::clone(&search_park);
+    let search_priority = warp::post()
+        .and(warp::path("search-priority"))
+        .and(warp::query::<ApiSearchPriorityQuery>())
+        .map(move |q: ApiSearchPriorityQuery| (q, Arc::clone(&search_park2)))
+        .and_then(set_search_priority);
+
+    let search_park2 = Arc::clone(&search_park);
+    let cancel_search = warp::delete()
+        .and(warp::path("search"))
+        .and(warp::query::<ApiResultsQuery>())
+        .map(move |id: ApiResultsQuery| (id, Arc::clone(&search_park2)))
+        .and_then(cancel_search);
+
+    let search_park2 = Arc::clone(&search_park);
+    let vote = warp::post()
+        .and(warp::path("vote"))
+        .and(warp::query::<ApiVoteQuery>())
+        .map(move |q: ApiVoteQuery| (q, Arc::clone(&search_park2)))
+        .and_then(vote);
+
+    let search_park2 = Arc::clone(&search_park);
+    let search_events = warp::get()
+        .and(warp::path("search-events"))
+        .and(warp::query::<ApiResultsQuery>())
+        .map(move |id: ApiResultsQuery| (id, Arc::clone(&search_park2)))
+        .and_then(search_events);
+
     let search_park2 = Arc::clone(&search_park);
     let results = warp::get()
         .and(warp::path("results"))
@@ -111,15 +348,40 @@ pub async fn serve_api(config: Arc<Args>, index: DocumentIndex, search_park: Arc
         .and(warp::path("fetch-results"))
         .and(warp::query::<ApiResultsQuery>())
         .map(move |id: ApiResultsQuery| (id, Arc::clone(&search_park2)))
-        .and_then(fetch_results);
+        .and_then(legacy_fetch_results);
 
+    let search_park2 = Arc::clone(&search_park);
     let config2 = Arc::clone(&config);
     let result = warp::get()
         .and(warp::path("result"))
         .and(warp::query::<ApiResultQuery>())
-        .map(move |q: ApiResultQuery| (q, Arc::clone(&search_park), Arc::clone(&config2)))
+        .map(move |q: ApiResultQuery| (q, Arc::clone(&search_park2), Arc::clone(&config2)))
         .and_then(get_result);
 
+    let kamilata2 = kamilata.clone();
+    let metrics = warp::get()
+        .and(warp::path("metrics"))
+        .map(move || (index.clone(), kamilata2.clone(), Arc::clone(&search_park)))
+        .and_then(metrics);
+
+    let kamilata2 = kamilata.clone();
+    let connected_peers = warp::get()
+        .and(warp::path("peers"))
+        .map(move || kamilata2.clone())
+        .and_then(connected_peers);
+
+    let kamilata2 = kamilata.clone();
+    let known_peers = warp::get()
+        .and(warp::path("known-peers"))
+        .map(move || kamilata2.clone())
+        .and_then(known_peers);
+
+    let config2 = Arc::clone(&config);
+    let swarm_status = warp::get()
+        .and(warp::path("swarm-status"))
+        .map(move || (kamilata.clone(), Arc::clone(&config2)))
+        .and_then(swarm_status);
+
     let version = warp::get()
         .and(warp::path("version"))
         .and_then(version);
@@ -136,10 +398,18 @@ pub async fn serve_api(config: Arc<Args>, index: DocumentIndex, search_park: Arc
             .or(indexing_status)
             .or(local_search)
             .or(search)
+            .or(search_priority)
+            .or(cancel_search)
+            .or(vote)
+            .or(search_events)
             .or(results)
             .or(fetch_results)
             .or(version)
             .or(result)
+            .or(connected_peers)
+            .or(known_peers)
+            .or(swarm_status)
+            .or(metrics)
     ).with(cors);
 
     warp::serve(routes).run(config.api_addr.parse::<SocketAddr>().expect("Invalid api_addr")).await;
@@ -0,0 +1,23 @@
+use super::*;
+
+pub(super) async fn connected_peers(kamilata: NodeController) -> Result<impl warp::Reply, Infallible> {
+    let peers = kamilata.sw.connected_peers_status().await;
+    Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&peers).unwrap()).unwrap())
+}
+
+pub(super) async fn known_peers(kamilata: NodeController) -> Result<impl warp::Reply, Infallible> {
+    let peers = kamilata.sw.known_peers_status().await;
+    Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&peers).unwrap()).unwrap())
+}
+
+pub(super) async fn swarm_status((kamilata, config): (NodeController, Arc<Args>)) -> Result<impl warp::Reply, Infallible> {
+    let mut external_addrs = config.external_addrs.clone().unwrap_or_default();
+    for addr in kamilata.external_addresses().await {
+        let addr = addr.to_string();
+        if !external_addrs.contains(&addr) {
+            external_addrs.push(addr);
+        }
+    }
+    let status = kamilata.sw.status(kamilata.peer_id(), external_addrs).await;
+    Ok(Response::builder().header("Content-Type", "application/json").body(serde_json::to_string(&status).unwrap()).unwrap())
+}
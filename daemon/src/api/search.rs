@@ -1,7 +1,47 @@
 use super::*;
 
-pub(super) async fn search((query, search_park, kamilata): (ApiSearchQuery, Arc<SearchPark>, NodeController)) -> Result<impl warp::Reply, Infallible> {
-    let query = match Query::parse(&query.q) {
+fn search_priority(priority: ApiSearchPriority, until_documents: Option<usize>) -> SearchPriority {
+    match priority {
+        ApiSearchPriority::Speed => SearchPriority::speed(),
+        ApiSearchPriority::Relevance => SearchPriority::relevance(),
+        ApiSearchPriority::Variable => SearchPriority::Variable {
+            first: Box::new(SearchPriority::speed()),
+            until_documents: until_documents.unwrap_or(25),
+            then: Box::new(SearchPriority::relevance()),
+        },
+    }
+}
+
+impl ApiSearchQuery {
+    /// Applies the optional parameters over the default [SearchConfig].
+    fn search_config(&self) -> Result<SearchConfig, &'static str> {
+        let mut config = SearchConfig::default();
+        if self.priority.is_some() || self.until_documents.is_some() {
+            let priority = self.priority.unwrap_or(ApiSearchPriority::Variable);
+            config = config.with_priority(search_priority(priority, self.until_documents));
+        }
+        if let Some(req_limit) = self.req_limit {
+            if req_limit == 0 {
+                return Err("req_limit must be at least 1");
+            }
+            config = config.with_req_limit(req_limit);
+        }
+        if let Some(timeout_ms) = self.timeout_ms {
+            if timeout_ms == 0 {
+                return Err("timeout_ms must be at least 1");
+            }
+            config = config.with_timeout_ms(timeout_ms);
+        }
+        Ok(config)
+    }
+}
+
+pub(super) async fn search((query, search_park, kamilata, index): (ApiSearchQuery, Arc<SearchPark>, NodeController, DocumentIndex)) -> Result<impl warp::Reply, Infallible> {
+    let config = match query.search_config() {
+        Ok(config) => config,
+        Err(e) => return Ok(Response::builder().status(400).body(e.to_string()).unwrap()),
+    };
+    let mut query = match Query::parse(&query.q) {
         Ok(query) => query,
         Err(e) => {
             error!("Error parsing query:");
@@ -9,8 +49,9 @@ pub(super) async fn search((query, search_park, kamilata): (ApiSearchQuery, Arc<
             return Ok(Response::builder().status(400).body("Error parsing query".to_string()).unwrap());
         },
     };
+    index.fill_known(&mut query).await;
     info!("Searching for {:?}", query);
-    let search_controler = kamilata.search(query.clone()).await;
+    let search_controler = kamilata.search(query.clone(), config).await;
     let id = search_park.insert(query.clone(), search_controler).await;
 
     let resp = Response::builder()
@@ -22,3 +63,25 @@ pub(super) async fn search((query, search_park, kamilata): (ApiSearchQuery, Arc<
         .unwrap();
     Ok(resp)
 }
+
+pub(super) async fn set_search_priority((query, search_park): (ApiSearchPriorityQuery, Arc<SearchPark>)) -> Result<impl warp::Reply, Infallible> {
+    let priority = search_priority(query.priority, query.until_documents);
+    match search_park.set_priority(query.id as usize, priority).await {
+        true => Ok(Response::builder().status(204).body(String::new()).unwrap()),
+        false => Ok(Response::builder().status(400).body("Search not found".to_string()).unwrap()),
+    }
+}
+
+pub(super) async fn cancel_search((query, search_park): (ApiResultsQuery, Arc<SearchPark>)) -> Result<impl warp::Reply, Infallible> {
+    match search_park.cancel(query.id as usize).await {
+        true => Ok(Response::builder().status(204).body(String::new()).unwrap()),
+        false => Ok(Response::builder().status(400).body("Search not found".to_string()).unwrap()),
+    }
+}
+
+pub(super) async fn vote((query, search_park): (ApiVoteQuery, Arc<SearchPark>)) -> Result<impl warp::Reply, Infallible> {
+    match search_park.vote(query.id as usize, &query.cid, query.vote).await {
+        true => Ok(Response::builder().status(204).body(String::new()).unwrap()),
+        false => Ok(Response::builder().status(400).body("Result not found".to_string()).unwrap()),
+    }
+}
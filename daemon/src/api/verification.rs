@@ -0,0 +1,86 @@
+use super::*;
+
+/// Time after which a result that couldn't be verified is delivered anyway
+const VERIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(super) enum Verification {
+    Agrees,
+    Disagrees,
+    /// The document couldn't be fetched or understood
+    Unavailable,
+}
+
+/// Whether the DNS link of a result leads to its document
+pub(super) enum DnsLink {
+    Correct,
+    Incorrect,
+    /// The result has no DNS link, or it couldn't be resolved
+    Unchecked,
+}
+
+pub(super) type VerificationFuture = Pin<Box<dyn Future<Output = (DocumentResult, PeerId, Verification, DnsLink)> + Send>>;
+
+/// Generates the result again from the document, and checks the provider didn't lie about it.
+pub(super) fn verify_result(result: DocumentResult, peer_id: PeerId, query: Arc<Query>, config: Arc<Args>) -> VerificationFuture {
+    Box::pin(async move {
+        let raw = match timeout(VERIFICATION_TIMEOUT, fetch_document(&config.ipfs_rpc, &result.cid)).await {
+            Ok(Ok(raw)) => Some(raw),
+            Ok(Err(e)) => {
+                debug!("Failed to fetch {} for verification: {e}", result.cid);
+                None
+            },
+            Err(_) => {
+                debug!("Timed out fetching {} for verification", result.cid);
+                None
+            },
+        };
+        // Paths are only used to detect the format, as some formats can't be sniffed
+        let verification = match raw.map(|raw| generate_result(raw, result.cid.clone(), &query, result.paths.clone())) {
+            Some(Some(trusted)) if result.agrees_with(&trusted) => Verification::Agrees,
+            Some(Some(_)) => Verification::Disagrees,
+            Some(None) => {
+                debug!("Couldn't generate a result from {} for verification", result.cid);
+                Verification::Unavailable
+            },
+            None => Verification::Unavailable,
+        };
+        // Liars are banned anyway
+        let dns_link = match verification {
+            Verification::Disagrees => DnsLink::Unchecked,
+            _ => check_dns_link(&result, &config).await,
+        };
+        (result, peer_id, verification, dns_link)
+    })
+}
+
+/// Path parts that can be sent to the RPC without escaping
+fn is_plain_path_part(part: &str) -> bool {
+    !part.is_empty() && part.bytes().all(|b| b.is_ascii_alphanumeric() || b"-._~".contains(&b))
+}
+
+/// Resolves the first DNS link of the result through IPNS, and checks it leads to the document.
+/// Paths starting with a domain name are DNS links, as built from DNS pins.
+async fn check_dns_link(result: &DocumentResult, config: &Args) -> DnsLink {
+    let Some(path) = result.paths.iter().find(|path| path.first().map(|first| first.contains('.')).unwrap_or(false)) else {
+        return DnsLink::Unchecked;
+    };
+    if !path.iter().all(|part| is_plain_path_part(part)) {
+        return DnsLink::Unchecked;
+    }
+    let path = format!("/ipns/{}", path.join("/"));
+    match timeout(VERIFICATION_TIMEOUT, resolve(&config.ipfs_rpc, &path)).await {
+        Ok(Ok(cid)) if cid == result.cid => DnsLink::Correct,
+        Ok(Ok(cid)) => {
+            debug!("{path} resolves to {cid} instead of {}", result.cid);
+            DnsLink::Incorrect
+        },
+        Ok(Err(e)) => {
+            debug!("Failed to resolve {path} for verification: {e}");
+            DnsLink::Unchecked
+        },
+        Err(_) => {
+            debug!("Timed out resolving {path} for verification");
+            DnsLink::Unchecked
+        },
+    }
+}
@@ -22,6 +22,16 @@ pub struct Args {
     #[arg(long, default_value = "false", action = Set)]
     pub ipfs_peers_enabled: bool,
 
+    /// Path to the file storing the node's identity keypair (libp2p protobuf encoding)
+    /// A new keypair is generated there on first run
+    #[arg(long, default_value = "admarus.key")]
+    pub keypair_path: String,
+
+    /// Path to an existing libp2p protobuf-encoded keypair to import
+    /// The imported keypair replaces the one stored at keypair_path
+    #[arg(long)]
+    pub import_keypair: Option<String>,
+
     /// Census public RPC url
     #[arg(long, default_value = "https://census.admarus.net")]
     pub census_rpc: String,
@@ -55,11 +65,40 @@ pub struct Args {
     #[arg(long, default_value = "50")]
     pub leechers: usize,
 
+    /// Size of our Bloom filter (in bytes)
+    /// Larger filters have less false positives on large corpora, but are heavier to send to leechers
+    #[arg(long, default_value = "125000", value_parser = clap::value_parser!(u32).range(1..=FILTER_SIZE as i64))]
+    pub filter_size: u32,
+
+    /// Number of bits set in the Bloom filter for each word
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u8).range(1..))]
+    pub filter_hash_count: u8,
+
+    /// Seed of the hashes used by the Bloom filter
+    #[arg(long, default_value = "0")]
+    pub filter_seed: u64,
+
+    /// Maximum number of results generated concurrently for a single query
+    /// Results that can't be generated from the index require fetching documents from IPFS
+    #[arg(long, default_value = "16")]
+    pub result_parallelism: usize,
+
+    /// Proportion of remote results to verify, between 0 and 1
+    /// Verified results are generated again from the document, fetched from IPFS, and providers of mismatching results are banned
+    #[arg(long, default_value = "0")]
+    pub verification_rate: f64,
+
     /// Whether to also crawl unprioritized documents
     /// Prioritized documents are documents named with a supported extension
     #[arg(long, default_value = "false", action = Set)]
     pub crawl_unprioritized: bool,
 
+    /// Path to the file storing known peers across restarts.
+    /// Known peers are stored in the database instead when there is one.
+    #[cfg_attr(not(any(feature = "database-lmdb", feature = "database-mdbx")), arg(long, default_value = "admarus-peers.json"))]
+    #[cfg(not(any(feature = "database-lmdb", feature = "database-mdbx")))]
+    pub known_peers_path: String,
+
     /// Path to the database.
     /// Admarus does not require using a database, which is fine under 10000 documents.
     #[cfg_attr(any(feature = "database-lmdb", feature = "database-mdbx"), arg(long, default_value = "admarus.mdb"))]
@@ -76,3 +115,10 @@ pub struct Args {
     #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
     pub database_max_readers: u32,
 }
+
+impl Args {
+    /// Parameters of the Bloom filter announced to leechers
+    pub fn filter_params(&self) -> FilterParams {
+        FilterParams::new(self.filter_size as usize, self.filter_hash_count, self.filter_seed)
+    }
+}
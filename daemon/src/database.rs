@@ -6,6 +6,10 @@ use bimap::BiHashMap;
 
 type LEU32 = U32<LE>;
 
+/// Version of the layout of the data stored in the database.
+/// Databases with a different version are cleared on startup, so that documents get indexed again.
+const DB_VERSION: u32 = 3;
+
 #[derive(Debug)]
 pub enum DbError {
     CommandChannelUnavailable,
@@ -25,27 +29,75 @@ pub struct DbController {
 }
 
 impl DbController {
-    async fn index_get(&self, keys: Vec<String>) -> Result<Vec<(String, Vec<(LocalCid, f32)>)>, DbError> {
+    async fn index_get(&self, keys: Vec<String>) -> Result<Vec<(String, Vec<(LocalCid, Posting)>)>, DbError> {
         let (sender, receiver) = oneshot_channel();
         self.sender.send(DbCommand::IndexGet{keys, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
         Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
     }
 
-    async fn index_put(&self, items: Vec<(String, HashMap<LocalCid, f32>)>) -> Result<(), DbError> {
+    async fn index_put(&self, items: Vec<(String, HashMap<LocalCid, Posting>)>) -> Result<(), DbError> {
         let (sender, receiver) = oneshot_channel();
         self.sender.send(DbCommand::IndexPut{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
         Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
     }
 
+    async fn filters_get(&self, keys: Vec<(String, String)>) -> Result<Vec<((String, String), Vec<LocalCid>)>, DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::FiltersGet{keys, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn filters_put(&self, items: Vec<((String, String), Vec<LocalCid>)>) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::FiltersPut{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
     async fn put_cids(&self, items: Vec<(LocalCid, String)>) -> Result<(), DbError> {
         let (sender, receiver) = oneshot_channel();
         self.sender.send(DbCommand::PutCids{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
         Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
     }
 
-    pub async fn compute_filter(&self) -> Result<Filter<FILTER_SIZE>, DbError> {
+    async fn summaries_get(&self, lcids: Vec<LocalCid>) -> Result<HashMap<LocalCid, DocumentSummary>, DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::SummariesGet{lcids, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn summaries_put(&self, items: Vec<(LocalCid, DocumentSummary)>) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::SummariesPut{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn peers_get(&self) -> Result<Vec<StoredPeerInfo>, DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::PeersGet{sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn peers_replace(&self, items: Vec<StoredPeerInfo>) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::PeersReplace{items, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    async fn remove_documents(&self, lcids: Vec<LocalCid>) -> Result<(), DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::RemoveDocuments{lcids, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    pub async fn compute_filter(&self, params: FilterParams) -> Result<Filter<FILTER_SIZE>, DbError> {
+        let (sender, receiver) = oneshot_channel();
+        self.sender.send(DbCommand::ComputeFilter{params, sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
+    }
+
+    pub async fn compute_vocabulary(&self) -> Result<Vocabulary, DbError> {
         let (sender, receiver) = oneshot_channel();
-        self.sender.send(DbCommand::ComputeFilter{sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
+        self.sender.send(DbCommand::ComputeVocabulary{sender}).await.map_err(|_| DbError::CommandChannelUnavailable)?;
         Ok(receiver.await.map_err(|_| DbError::UnresponsiveDatabase)??)
     }
 }
@@ -54,18 +106,41 @@ impl DbController {
 /// A [DbController] that is restricted to index-related commands
 pub struct DbIndexController(DbController);
 impl DbIndexController {
-    pub async fn get(&self, keys: Vec<String>) -> Result<Vec<(String, Vec<(LocalCid, f32)>)>, DbError> { self.0.index_get(keys).await }
-    pub async fn put(&self, items: Vec<(String, HashMap<LocalCid, f32>)>) -> Result<(), DbError> { self.0.index_put(items).await }
+    pub async fn get(&self, keys: Vec<String>) -> Result<Vec<(String, Vec<(LocalCid, Posting)>)>, DbError> { self.0.index_get(keys).await }
+    pub async fn put(&self, items: Vec<(String, HashMap<LocalCid, Posting>)>) -> Result<(), DbError> { self.0.index_put(items).await }
+    pub async fn get_filters(&self, keys: Vec<(String, String)>) -> Result<Vec<((String, String), Vec<LocalCid>)>, DbError> { self.0.filters_get(keys).await }
+    pub async fn put_filters(&self, items: Vec<((String, String), Vec<LocalCid>)>) -> Result<(), DbError> { self.0.filters_put(items).await }
     pub async fn put_cids(&self, items: Vec<(LocalCid, String)>) -> Result<(), DbError> { self.0.put_cids(items).await }
-    pub async fn compute_filter(&self) -> Result<Filter<FILTER_SIZE>, DbError> { self.0.compute_filter().await }
+    pub async fn get_summaries(&self, lcids: Vec<LocalCid>) -> Result<HashMap<LocalCid, DocumentSummary>, DbError> { self.0.summaries_get(lcids).await }
+    pub async fn put_summaries(&self, items: Vec<(LocalCid, DocumentSummary)>) -> Result<(), DbError> { self.0.summaries_put(items).await }
+    pub async fn remove_documents(&self, lcids: Vec<LocalCid>) -> Result<(), DbError> { self.0.remove_documents(lcids).await }
+    pub async fn compute_filter(&self, params: FilterParams) -> Result<Filter<FILTER_SIZE>, DbError> { self.0.compute_filter(params).await }
+    pub async fn compute_vocabulary(&self) -> Result<Vocabulary, DbError> { self.0.compute_vocabulary().await }
 }
 impl From<DbController> for DbIndexController { fn from(controller: DbController) -> Self { DbIndexController(controller) } }
 
+#[derive(Clone)]
+/// A [DbController] that is restricted to the known peers of the swarm
+pub struct DbPeersController(DbController);
+impl DbPeersController {
+    pub async fn get(&self) -> Result<Vec<StoredPeerInfo>, DbError> { self.0.peers_get().await }
+    pub async fn replace(&self, items: Vec<StoredPeerInfo>) -> Result<(), DbError> { self.0.peers_replace(items).await }
+}
+impl From<DbController> for DbPeersController { fn from(controller: DbController) -> Self { DbPeersController(controller) } }
+
 enum DbCommand {
-    IndexGet { keys: Vec<String>, sender: OneshotSender<Result<Vec<(String, Vec<(LocalCid, f32)>)>, HeedError>> },
-    IndexPut { items: Vec<(String, HashMap<LocalCid, f32>)>, sender: OneshotSender<Result<(), HeedError>> },
+    IndexGet { keys: Vec<String>, sender: OneshotSender<Result<Vec<(String, Vec<(LocalCid, Posting)>)>, HeedError>> },
+    IndexPut { items: Vec<(String, HashMap<LocalCid, Posting>)>, sender: OneshotSender<Result<(), HeedError>> },
+    FiltersGet { keys: Vec<(String, String)>, sender: OneshotSender<Result<Vec<((String, String), Vec<LocalCid>)>, HeedError>> },
+    FiltersPut { items: Vec<((String, String), Vec<LocalCid>)>, sender: OneshotSender<Result<(), HeedError>> },
     PutCids { items: Vec<(LocalCid, String)>, sender: OneshotSender<Result<(), HeedError>> },
-    ComputeFilter { sender: OneshotSender<Result<Filter<FILTER_SIZE>, HeedError>> },
+    SummariesGet { lcids: Vec<LocalCid>, sender: OneshotSender<Result<HashMap<LocalCid, DocumentSummary>, HeedError>> },
+    SummariesPut { items: Vec<(LocalCid, DocumentSummary)>, sender: OneshotSender<Result<(), HeedError>> },
+    PeersGet { sender: OneshotSender<Result<Vec<StoredPeerInfo>, HeedError>> },
+    PeersReplace { items: Vec<StoredPeerInfo>, sender: OneshotSender<Result<(), HeedError>> },
+    RemoveDocuments { lcids: Vec<LocalCid>, sender: OneshotSender<Result<(), HeedError>> },
+    ComputeFilter { params: FilterParams, sender: OneshotSender<Result<Filter<FILTER_SIZE>, HeedError>> },
+    ComputeVocabulary { sender: OneshotSender<Result<Vocabulary, HeedError>> },
 }
 
 impl std::fmt::Debug for DbCommand {
@@ -73,37 +148,105 @@ impl std::fmt::Debug for DbCommand {
         match self {
             DbCommand::IndexGet { keys, .. } => f.debug_struct("IndexGet").field("keys", &format!("{:?} entries", keys.len())).finish_non_exhaustive(),
             DbCommand::IndexPut { items, .. } => f.debug_struct("IndexPut").field("index", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
+            DbCommand::FiltersGet { keys, .. } => f.debug_struct("FiltersGet").field("keys", &format!("{:?} entries", keys.len())).finish_non_exhaustive(),
+            DbCommand::FiltersPut { items, .. } => f.debug_struct("FiltersPut").field("filters", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
             DbCommand::PutCids { items, .. } => f.debug_struct("PutCids").field("cids", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
+            DbCommand::SummariesGet { lcids, .. } => f.debug_struct("SummariesGet").field("lcids", &format!("{:?} entries", lcids.len())).finish_non_exhaustive(),
+            DbCommand::SummariesPut { items, .. } => f.debug_struct("SummariesPut").field("summaries", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
+            DbCommand::PeersGet { .. } => f.debug_struct("PeersGet").finish_non_exhaustive(),
+            DbCommand::PeersReplace { items, .. } => f.debug_struct("PeersReplace").field("peers", &format!("{:?} entries", items.len())).finish_non_exhaustive(),
+            DbCommand::RemoveDocuments { lcids, .. } => f.debug_struct("RemoveDocuments").field("lcids", &format!("{:?} entries", lcids.len())).finish_non_exhaustive(),
             DbCommand::ComputeFilter { .. } => f.debug_struct("ComputeFilter").finish_non_exhaustive(),
+            DbCommand::ComputeVocabulary { .. } => f.debug_struct("ComputeVocabulary").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Postings are stored one after the other as:
+/// lcid (u32 LE), frequency (f32 LE), position count (u32 LE), positions (u32 LE each)
+fn decode_postings(data: &[u8]) -> Vec<(LocalCid, Posting)> {
+    fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    let mut postings = Vec::new();
+    let mut offset = 0;
+    while let (Some(lcid), Some(frequency), Some(count)) = (read_u32(data, offset), read_u32(data, offset + 4), read_u32(data, offset + 8)) {
+        offset += 12;
+        let mut positions = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let Some(position) = read_u32(data, offset) else {
+                warn!("Truncated postings in database");
+                return postings;
+            };
+            positions.push(position);
+            offset += 4;
+        }
+        postings.push((LocalCid(lcid), Posting { frequency: f32::from_bits(frequency), positions }));
+    }
+    postings
+}
+
+fn encode_postings(postings: &HashMap<LocalCid, Posting>) -> Vec<u8> {
+    let mut data = Vec::with_capacity(postings.values().map(|p| 12 + p.positions.len() * 4).sum());
+    for (lcid, posting) in postings {
+        data.extend_from_slice(&lcid.0.to_le_bytes());
+        data.extend_from_slice(&posting.frequency.to_le_bytes());
+        data.extend_from_slice(&(posting.positions.len() as u32).to_le_bytes());
+        for position in &posting.positions {
+            data.extend_from_slice(&position.to_le_bytes());
         }
     }
+    data
 }
 
-fn index_get(keys: Vec<String>, env: &Env, index: &HeedDatabase<Str, ByteSlice>) -> Result<Vec<(String, Vec<(LocalCid, f32)>)>, HeedError> {
+fn index_get(keys: Vec<String>, env: &Env, index: &HeedDatabase<Str, ByteSlice>) -> Result<Vec<(String, Vec<(LocalCid, Posting)>)>, HeedError> {
     let rotxn = env.read_txn()?;
     let mut items = Vec::with_capacity(keys.len());
     for key in keys {
         let data = index.get(&rotxn, &key)?.unwrap_or_default();
-        let mut value = Vec::with_capacity(data.len() / 8);
-        for chunk in data.chunks_exact(8) {
-            let lcid: u32 = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-            let score: f32 = f32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
-            value.push((LocalCid(lcid), score));
-        }
-        items.push((key, value));
+        items.push((key, decode_postings(data)));
     }
     Ok(items)
 }
 
-fn index_put(items: &[(String, HashMap<LocalCid, f32>)], env: &Env, index: &HeedDatabase<Str, ByteSlice>) -> Result<(), HeedError> {
+fn index_put(items: &[(String, HashMap<LocalCid, Posting>)], env: &Env, index: &HeedDatabase<Str, ByteSlice>) -> Result<(), HeedError> {
     let mut wtxn = env.write_txn()?;
     for (key, value) in items {
-        let mut data = Vec::with_capacity(value.len() * 8);
-        for (lcid, score) in value {
-            data.extend_from_slice(&lcid.0.to_le_bytes());
-            data.extend_from_slice(&score.to_le_bytes());
-        }
-        index.put(&mut wtxn, key, &data)?;
+        index.put(&mut wtxn, key, &encode_postings(value))?;
+    }
+    wtxn.commit()?;
+    Ok(())
+}
+
+/// Filters are stored under their `name=value` word, which is also what goes in the Bloom filter.
+fn filter_key(name: &str, value: &str) -> String {
+    format!("{name}={value}")
+}
+
+fn decode_lcids(data: &[u8]) -> Vec<LocalCid> {
+    data.chunks_exact(4).map(|b| LocalCid(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))).collect()
+}
+
+fn encode_lcids(lcids: &[LocalCid]) -> Vec<u8> {
+    lcids.iter().flat_map(|lcid| lcid.0.to_le_bytes()).collect()
+}
+
+fn filters_get(keys: Vec<(String, String)>, env: &Env, filters: &HeedDatabase<Str, ByteSlice>) -> Result<Vec<((String, String), Vec<LocalCid>)>, HeedError> {
+    let rotxn = env.read_txn()?;
+    let mut items = Vec::with_capacity(keys.len());
+    for (name, value) in keys {
+        let data = filters.get(&rotxn, &filter_key(&name, &value))?.unwrap_or_default();
+        items.push(((name, value), decode_lcids(data)));
+    }
+    Ok(items)
+}
+
+fn filters_put(items: &[((String, String), Vec<LocalCid>)], env: &Env, filters: &HeedDatabase<Str, ByteSlice>) -> Result<(), HeedError> {
+    let mut wtxn = env.write_txn()?;
+    for ((name, value), lcids) in items {
+        filters.put(&mut wtxn, &filter_key(name, value), &encode_lcids(lcids))?;
     }
     wtxn.commit()?;
     Ok(())
@@ -118,19 +261,125 @@ fn put_cids(items: Vec<(LocalCid, String)>, env: &Env, cids: &HeedDatabase<Owned
     Ok(())
 }
 
-fn compute_filter(env: &Env, index: &HeedDatabase<Str, ByteSlice>) -> Result<Filter<FILTER_SIZE>, HeedError> {
-    let mut filter = Filter::new();
+/// Summaries are stored as JSON
+fn summaries_get(lcids: Vec<LocalCid>, env: &Env, summaries: &HeedDatabase<OwnedType<LEU32>, ByteSlice>) -> Result<HashMap<LocalCid, DocumentSummary>, HeedError> {
+    let rotxn = env.read_txn()?;
+    let mut items = HashMap::with_capacity(lcids.len());
+    for lcid in lcids {
+        let Some(data) = summaries.get(&rotxn, &LEU32::new(lcid.0))? else {continue};
+        match serde_json::from_slice(data) {
+            Ok(summary) => { items.insert(lcid, summary); },
+            Err(e) => warn!("Invalid summary in database for {lcid:?}: {e}"),
+        }
+    }
+    Ok(items)
+}
+
+fn summaries_put(items: Vec<(LocalCid, DocumentSummary)>, env: &Env, summaries: &HeedDatabase<OwnedType<LEU32>, ByteSlice>) -> Result<(), HeedError> {
+    let mut wtxn = env.write_txn()?;
+    for (lcid, summary) in items {
+        let data = serde_json::to_vec(&summary).expect("Summaries are always serializable");
+        summaries.put(&mut wtxn, &LEU32::new(lcid.0), &data)?;
+    }
+    wtxn.commit()?;
+    Ok(())
+}
+
+/// Peers are stored as JSON, keyed by peer id
+fn peers_get(env: &Env, peers: &HeedDatabase<Str, ByteSlice>) -> Result<Vec<StoredPeerInfo>, HeedError> {
+    let rotxn = env.read_txn()?;
+    let mut items = Vec::new();
+    for entry in peers.iter(&rotxn)? {
+        let (peer_id, data) = entry?;
+        match serde_json::from_slice(data) {
+            Ok(peer) => items.push(peer),
+            Err(e) => warn!("Invalid known peer in database for {peer_id}: {e}"),
+        }
+    }
+    Ok(items)
+}
+
+fn peers_replace(items: Vec<StoredPeerInfo>, env: &Env, peers: &HeedDatabase<Str, ByteSlice>) -> Result<(), HeedError> {
+    let mut wtxn = env.write_txn()?;
+    peers.clear(&mut wtxn)?;
+    for peer in items {
+        let data = serde_json::to_vec(&peer).expect("Known peers are always serializable");
+        peers.put(&mut wtxn, &peer.peer_id, &data)?;
+    }
+    wtxn.commit()?;
+    Ok(())
+}
+
+/// Removes documents from every table.
+/// Postings don't tell which words a document contains, so the whole index is scanned.
+fn remove_documents(lcids: Vec<LocalCid>, env: &Env, index: &HeedDatabase<Str, ByteSlice>, filters: &HeedDatabase<Str, ByteSlice>, cids: &HeedDatabase<OwnedType<LEU32>, Str>, summaries: &HeedDatabase<OwnedType<LEU32>, ByteSlice>) -> Result<(), HeedError> {
+    let lcids = lcids.into_iter().collect::<HashSet<_>>();
+    let mut wtxn = env.write_txn()?;
+    for lcid in &lcids {
+        cids.delete(&mut wtxn, &LEU32::new(lcid.0))?;
+        summaries.delete(&mut wtxn, &LEU32::new(lcid.0))?;
+    }
+
+    let mut index_updates = Vec::new();
+    for item in index.iter(&wtxn)? {
+        let (word, data) = item?;
+        let postings = decode_postings(data);
+        if postings.iter().any(|(lcid, _)| lcids.contains(lcid)) {
+            let postings = postings.into_iter().filter(|(lcid, _)| !lcids.contains(lcid)).collect::<HashMap<_, _>>();
+            index_updates.push((word.to_owned(), postings));
+        }
+    }
+    for (word, postings) in index_updates {
+        match postings.is_empty() {
+            true => { index.delete(&mut wtxn, &word)?; },
+            false => index.put(&mut wtxn, &word, &encode_postings(&postings))?,
+        }
+    }
+
+    let mut filter_updates = Vec::new();
+    for item in filters.iter(&wtxn)? {
+        let (key, data) = item?;
+        let filtered = decode_lcids(data);
+        if filtered.iter().any(|lcid| lcids.contains(lcid)) {
+            let filtered = filtered.into_iter().filter(|lcid| !lcids.contains(lcid)).collect::<Vec<_>>();
+            filter_updates.push((key.to_owned(), filtered));
+        }
+    }
+    for (key, filtered) in filter_updates {
+        match filtered.is_empty() {
+            true => { filters.delete(&mut wtxn, &key)?; },
+            false => filters.put(&mut wtxn, &key, &encode_lcids(&filtered))?,
+        }
+    }
+
+    wtxn.commit()?;
+    Ok(())
+}
+
+fn compute_filter(params: FilterParams, env: &Env, index: &HeedDatabase<Str, ByteSlice>, filters: &HeedDatabase<Str, ByteSlice>) -> Result<Filter<FILTER_SIZE>, HeedError> {
+    let mut filter = Filter::with_params(params);
 
     let rotxn = env.read_txn().expect("Failed to open read transaction to iterate over words");
     let index = index.iter(&rotxn).expect("Failed to iterate over words").filter_map(|c| c.ok());
     for (word, _) in index {
         filter.add_word::<DocumentIndex>(word);
     }
+    let filters = filters.iter(&rotxn).expect("Failed to iterate over filters").filter_map(|c| c.ok());
+    for (key, _) in filters {
+        filter.add_word::<DocumentIndex>(key);
+    }
 
     Ok(filter)
 }
 
-fn run_database(env: Env, index: HeedDatabase<Str, ByteSlice>, cids: HeedDatabase<OwnedType<LEU32>, Str>, mut receiver: Receiver<DbCommand>) {
+/// Words are iterated in lexicographic order, which is what the vocabulary needs.
+fn compute_vocabulary(env: &Env, index: &HeedDatabase<Str, ByteSlice>) -> Result<Vocabulary, HeedError> {
+    let rotxn = env.read_txn()?;
+    let words = index.iter(&rotxn)?.filter_map(|c| c.ok()).map(|(word, _)| word);
+    Ok(Vocabulary::from_sorted(words))
+}
+
+fn run_database(env: Env, index: HeedDatabase<Str, ByteSlice>, filters: HeedDatabase<Str, ByteSlice>, cids: HeedDatabase<OwnedType<LEU32>, Str>, summaries: HeedDatabase<OwnedType<LEU32>, ByteSlice>, peers: HeedDatabase<Str, ByteSlice>, mut receiver: Receiver<DbCommand>) {
     loop {
         // Receive command
         let Some(command) = block_on(receiver.recv()) else {
@@ -150,16 +399,56 @@ fn run_database(env: Env, index: HeedDatabase<Str, ByteSlice>, cids: HeedDatabas
                 let r = sender.send(result);
                 if let Err(e) = r { error!("Failed to send index database write result: {e:?}") }
             },
+            DbCommand::FiltersGet { keys, sender } => {
+                let result = filters_get(keys, &env, &filters);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send filters database read result: {e:?}") }
+            },
+            DbCommand::FiltersPut { items, sender } => {
+                let result = filters_put(&items, &env, &filters);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send filters database write result: {e:?}") }
+            },
             DbCommand::PutCids { items, sender } => {
                 let result = put_cids(items, &env, &cids);
                 let r = sender.send(result);
                 if let Err(e) = r { error!("Failed to send cids database write result: {e:?}") }
             },
-            DbCommand::ComputeFilter { sender } => {
-                let result = compute_filter(&env, &index);
+            DbCommand::SummariesGet { lcids, sender } => {
+                let result = summaries_get(lcids, &env, &summaries);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send summaries database read result: {e:?}") }
+            },
+            DbCommand::SummariesPut { items, sender } => {
+                let result = summaries_put(items, &env, &summaries);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send summaries database write result: {e:?}") }
+            },
+            DbCommand::PeersGet { sender } => {
+                let result = peers_get(&env, &peers);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send peers database read result: {e:?}") }
+            },
+            DbCommand::PeersReplace { items, sender } => {
+                let result = peers_replace(items, &env, &peers);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send peers database write result: {e:?}") }
+            },
+            DbCommand::RemoveDocuments { lcids, sender } => {
+                let result = remove_documents(lcids, &env, &index, &filters, &cids, &summaries);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send document removal result: {e:?}") }
+            },
+            DbCommand::ComputeFilter { params, sender } => {
+                let result = compute_filter(params, &env, &index, &filters);
                 let r = sender.send(result);
                 if let Err(e) = r { error!("Failed to send filter computation result: {e:?}") }
             },
+            DbCommand::ComputeVocabulary { sender } => {
+                let result = compute_vocabulary(&env, &index);
+                let r = sender.send(result);
+                if let Err(e) = r { error!("Failed to send vocabulary computation result: {e:?}") }
+            },
         }
     }
 }
@@ -178,10 +467,30 @@ pub fn open_database(config: Arc<Args>) -> (DbController, u32, BiHashMap<LocalCi
 
     // Create databases
     let mut wtxn = env.write_txn().expect("Failed to open write transaction for database creation");
-    let index = env.create_database(&mut wtxn, Some("index")).expect("Failed to create index database");
+    let index: HeedDatabase<Str, ByteSlice> = env.create_database(&mut wtxn, Some("index")).expect("Failed to create index database");
     let cid_db: HeedDatabase<OwnedType<LEU32>, Str> = env.create_database(&mut wtxn, Some("cids")).expect("Failed to create cids database");
+    let filters_db: HeedDatabase<Str, ByteSlice> = env.create_database(&mut wtxn, Some("filters")).expect("Failed to create filters database");
+    let summaries_db: HeedDatabase<OwnedType<LEU32>, ByteSlice> = env.create_database(&mut wtxn, Some("summaries")).expect("Failed to create summaries database");
+    let peers_db: HeedDatabase<Str, ByteSlice> = env.create_database(&mut wtxn, Some("peers")).expect("Failed to create peers database");
+    let meta_db: HeedDatabase<Str, OwnedType<LEU32>> = env.create_database(&mut wtxn, Some("meta")).expect("Failed to create meta database");
     wtxn.commit().expect("Failed to commit write transaction for database creation");
 
+    // Clear data stored in an outdated layout
+    let mut wtxn = env.write_txn().expect("Failed to open write transaction for version check");
+    let version = meta_db.get(&wtxn, "version").expect("Failed to read database version").map(|v| v.get());
+    let is_empty = cid_db.is_empty(&wtxn).expect("Failed to check if cids database is empty");
+    if version != Some(DB_VERSION) {
+        if !is_empty {
+            warn!("Database layout is outdated ({version:?} != {DB_VERSION}), documents will be indexed again");
+            index.clear(&mut wtxn).expect("Failed to clear index database");
+            cid_db.clear(&mut wtxn).expect("Failed to clear cids database");
+            filters_db.clear(&mut wtxn).expect("Failed to clear filters database");
+            summaries_db.clear(&mut wtxn).expect("Failed to clear summaries database");
+        }
+        meta_db.put(&mut wtxn, "version", &LEU32::new(DB_VERSION)).expect("Failed to write database version");
+    }
+    wtxn.commit().expect("Failed to commit write transaction for version check");
+
     // Retrieve all cids
     let start = Instant::now();
     let rotxn = env.read_txn().expect("Failed to open read transaction for cid restoration");
@@ -201,7 +510,7 @@ pub fn open_database(config: Arc<Args>) -> (DbController, u32, BiHashMap<LocalCi
     }
 
     let (sender, receiver) = channel(200);    
-    std::thread::spawn(move || run_database(env, index, cid_db, receiver));
+    std::thread::spawn(move || run_database(env, index, filters_db, cid_db, summaries_db, peers_db, receiver));
 
     (DbController{sender}, max+100_000 /* TODO: refine value */, cids)
 }
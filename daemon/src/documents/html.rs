@@ -0,0 +1,126 @@
+use scraper::{Selector, Html, ElementRef};
+use super::*;
+
+pub(super) fn inspect_document_html(raw: &str) -> Option<DocumentInspectionReport> {
+    let document = Html::parse_document(raw);
+    let mut filters = HashMap::new();
+
+    // Get lang
+    let html_selector = Selector::parse("html").expect("Invalid html selector");
+    let html_el = document.select(&html_selector).next();
+    let lang = html_el
+        .and_then(|el| el.value().attr("lang").map(|lang| lang.trim()))
+        .and_then(|l| l.split('-').next())
+        .map(|l| l.to_string())
+        .unwrap_or(String::from("unknown"));
+
+    // Retrieve title
+    let title_selector = Selector::parse("title").expect("Invalid title selector");
+    let title_el = document.select(&title_selector).next();
+    let mut title = title_el.map(|el| el.text().collect::<Vec<_>>().join(" "));
+    if title.as_ref().map(|t| t.trim().is_empty()).unwrap_or(false) {
+        title = None;
+    }
+
+    // Retrieve h1
+    let mut h1 = None;
+    if title.is_none() {
+        let h1_selector = Selector::parse("h1").expect("Invalid h1 selector");
+        let h1_el = document.select(&h1_selector).next();
+        h1 = h1_el.map(|el| el.text().collect::<Vec<_>>().join(" "));
+        if h1.as_ref().map(|t| t.trim().is_empty()).unwrap_or(false) {
+            h1 = None;
+        }
+    }
+
+    // Retrieve favicons
+    let favicon_selector = Selector::parse(r#"html>head>link[rel="icon"], html>head>link[rel="shortcut icon"], html>head>link[rel="apple-touch-icon"], html>head>link[rel="apple-touch-icon-precomposed"]"#).expect("Invalid icon selector");
+    let mut favicons = Vec::new();
+    for favicon_el in document.select(&favicon_selector) {
+        let Some(href) = favicon_el.value().attr("href").map(|href| href.to_string()) else {continue};
+        let Some(mime_type) = favicon_el.value().attr("type").map(|mime_type| mime_type.to_string()) else {continue};
+        let Some(sizes) = favicon_el.value().attr("sizes").map(|sizes| sizes.to_string()) else {continue};
+        favicons.push(FaviconDescriptor { href, mime_type, sizes });
+    }
+
+    // Retrieve description
+    let description_selector = Selector::parse("meta[name=description]").expect("Invalid description selector");
+    let description_el = document.select(&description_selector).next();
+    let description = description_el.and_then(|el| el.value().attr("content").map(|c| c.to_string()));
+
+    // Retrieve images and videos
+    /*fn list_media(el: ElementRef, media: &mut Vec<StructuredData>) {
+        'init: {match el.value().name() {
+            "img" => {
+                let Some(src) = el.value().attr("src").map(|s| s.to_string()) else {break 'init};
+                let mut new_media = schemas::types::ImageObject::new();
+                new_media.media_object.set_content_url(SchemaUrl::from(src));
+                if let Some(alt) = el.value().attr("alt") {
+                    new_media.media_object.creative_work.thing.set_description(SchemaText::from(alt));
+                }
+                media.push(StructuredData::ImageObject(new_media));
+            },
+            "video" | "picture" => {
+                // TODO
+            },
+            _ => (),
+        }}
+        for child in el.children() {
+            if let scraper::node::Node::Element(_) = child.value() {
+                let child_ref = ElementRef::wrap(child).expect("Child isn't an element");
+                list_media(child_ref, media)
+            }
+        }
+    }
+    let mut media = Vec::new();
+    if let Some(body_el) = body_el {
+        list_media(body_el, &mut media);
+    }*/
+
+    // Count words
+    #[allow(clippy::too_many_arguments)]
+    fn count_words(
+        el: ElementRef, summary: &mut SummaryBuilder,
+        mut h1: bool, mut h2: bool, mut h3: bool, mut h4: bool, mut h5: bool, mut h6: bool, mut strong: bool, mut em: bool, mut small: bool, mut s: bool
+    ) {
+        match el.value().name() {
+            "h1" => h1 = true,
+            "h2" => h2 = true,
+            "h3" => h3 = true,
+            "h4" => h4 = true,
+            "h5" => h5 = true,
+            "h6" => h6 = true,
+            "strong" => strong = true,
+            "em" => em = true,
+            "small" => small = true,
+            "s" => s = true,
+            "script" | "style" => return,
+            _ => (),
+        }
+        for child in el.children() {
+            match child.value() {
+                scraper::node::Node::Element(_) => {
+                    let child_ref = ElementRef::wrap(child).expect("Child isn't an element");
+                    count_words(child_ref, summary, h1, h2, h3, h4, h5, h6, strong, em, small, s)
+                },
+                scraper::node::Node::Text(text) => {
+                    summary.add_fragment(text);
+                    for word in tokenizer::tokenize(text) {
+                        summary.add_word(word, |word_count| word_count.add(h1, h2, h3, h4, h5, h6, strong, em, small, s));
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+    let mut summary = SummaryBuilder::new(lang.clone());
+    let body_selector = Selector::parse("body").expect("Invalid body selector");
+    if let Some(body_el) = document.select(&body_selector).next() {
+        count_words(body_el, &mut summary, false, false, false, false, false, false, false, false, false, false);
+    }
+    let (words, summary) = summary.finish(title, h1, description, favicons);
+
+    filters.insert("lang", lang);
+
+    Some(DocumentInspectionReport { words, filters, summary })
+}
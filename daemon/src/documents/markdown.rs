@@ -0,0 +1,87 @@
+use pulldown_cmark::{Event, HeadingLevel, MetadataBlockKind, Options, Parser, Tag, TagEnd};
+use super::*;
+
+/// Markdown documents are titled after their front matter or their first heading.
+pub(super) fn parse_markdown(raw: &str) -> TextDocument {
+    let mut document = TextDocument::default();
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+
+    let mut style = TextStyle::default();
+    let (mut strong_depth, mut em_depth, mut s_depth) = (0, 0, 0);
+    let mut in_metadata = false;
+    let mut paragraph = String::new();
+    let mut heading = String::new();
+    let mut first_h1 = None;
+    let mut first_heading = None;
+
+    for event in Parser::new_ext(raw, options) {
+        match event {
+            Event::Start(Tag::MetadataBlock(MetadataBlockKind::YamlStyle)) => in_metadata = true,
+            Event::End(TagEnd::MetadataBlock(_)) => in_metadata = false,
+            Event::Start(Tag::Heading { level, .. }) => style.heading = Some(heading_level(level)),
+            Event::End(TagEnd::Heading(level)) => {
+                style.heading = None;
+                let heading = std::mem::take(&mut heading).trim().to_string();
+                if !heading.is_empty() {
+                    if level == HeadingLevel::H1 && first_h1.is_none() {
+                        first_h1 = Some(heading.clone());
+                    }
+                    first_heading.get_or_insert(heading);
+                }
+            },
+            Event::Start(Tag::Strong) => strong_depth += 1,
+            Event::End(TagEnd::Strong) => strong_depth -= 1,
+            Event::Start(Tag::Emphasis) => em_depth += 1,
+            Event::End(TagEnd::Emphasis) => em_depth -= 1,
+            Event::Start(Tag::Strikethrough) => s_depth += 1,
+            Event::End(TagEnd::Strikethrough) => s_depth -= 1,
+            Event::End(TagEnd::Paragraph | TagEnd::Item | TagEnd::CodeBlock | TagEnd::TableCell) => {
+                let paragraph = std::mem::take(&mut paragraph).split_whitespace().collect::<Vec<_>>().join(" ");
+                if !paragraph.is_empty() {
+                    document.paragraphs.push(paragraph);
+                }
+            },
+            Event::Text(text) if in_metadata => {
+                for line in text.lines() {
+                    let Some((key, value)) = line.split_once(':') else {continue};
+                    let value = value.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+                    match key.trim() {
+                        "title" => document.title = Some(value),
+                        "description" => document.description = Some(value),
+                        "lang" | "language" => document.lang = value.split('-').next().map(|l| l.to_string()),
+                        _ => (),
+                    }
+                }
+            },
+            Event::Text(text) | Event::Code(text) => {
+                style.strong = strong_depth > 0;
+                style.em = em_depth > 0;
+                style.s = s_depth > 0;
+                match style.heading.is_some() {
+                    true => heading.push_str(&text),
+                    false => paragraph.push_str(&text),
+                }
+                document.spans.push((text.into_string(), style));
+            },
+            Event::SoftBreak | Event::HardBreak => paragraph.push(' '),
+            _ => (),
+        }
+    }
+
+    if document.title.is_none() {
+        document.title = first_h1.or(first_heading).or_else(|| first_line(raw));
+    }
+
+    document
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
@@ -0,0 +1,132 @@
+use crate::prelude::*;
+
+mod html;
+mod markdown;
+mod pdf;
+mod summary;
+mod text;
+use html::*;
+use markdown::*;
+use pdf::*;
+pub use summary::*;
+use text::*;
+
+pub struct DocumentInspectionReport {
+    pub words: Vec<String>,
+    pub filters: HashMap<&'static str, String>,
+    pub summary: DocumentSummary,
+}
+
+/// Formats of documents we know how to index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Html,
+    Markdown,
+    Text,
+    Pdf,
+}
+
+impl DocumentFormat {
+    /// Guesses the format of a document from its file name.
+    pub fn from_name(name: &str) -> Option<DocumentFormat> {
+        let (_, extension) = name.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "html" | "htm" | "xhtml" => Some(DocumentFormat::Html),
+            "md" | "markdown" => Some(DocumentFormat::Markdown),
+            "txt" => Some(DocumentFormat::Text),
+            "pdf" => Some(DocumentFormat::Pdf),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a document from its first bytes.
+    /// Only formats with a recognizable signature can be detected this way.
+    pub fn sniff(raw: &[u8]) -> Option<DocumentFormat> {
+        if raw.starts_with(b"%PDF-") {
+            return Some(DocumentFormat::Pdf);
+        }
+
+        let start = String::from_utf8_lossy(&raw[..raw.len().min(512)]);
+        let start = start.trim_start_matches('\u{feff}').trim_start().to_ascii_lowercase();
+        if start.starts_with("<!doctype html") || start.starts_with("<html") {
+            return Some(DocumentFormat::Html);
+        }
+
+        None
+    }
+
+    /// Uses the file name if it is known, and falls back to sniffing the content.
+    pub fn detect(name: Option<&str>, raw: &[u8]) -> Option<DocumentFormat> {
+        name.and_then(DocumentFormat::from_name).or_else(|| DocumentFormat::sniff(raw))
+    }
+}
+
+pub fn inspect_document(raw: Vec<u8>, name: Option<&str>) -> Option<DocumentInspectionReport> {
+    match DocumentFormat::detect(name, &raw)? {
+        DocumentFormat::Html => inspect_document_html(&String::from_utf8_lossy(&raw)),
+        DocumentFormat::Markdown => inspect_text_document(parse_markdown(&String::from_utf8_lossy(&raw))),
+        DocumentFormat::Text => inspect_text_document(parse_text(&String::from_utf8_lossy(&raw))),
+        DocumentFormat::Pdf => inspect_text_document(parse_pdf(&raw)?),
+    }
+}
+
+/// Generates a result from a document that isn't in the index.
+/// Indexed documents have a [DocumentSummary] that should be used instead.
+pub fn generate_result(raw: Vec<u8>, cid: String, query: &Query, paths: Vec<Vec<String>>) -> Option<DocumentResult> {
+    let name = paths.iter().find_map(|path| path.last()).map(|name| name.as_str());
+    let mut result = inspect_document(raw, name)?.summary.to_result(query)?;
+    result.cid = cid;
+    result.paths = paths;
+
+    Some(result)
+}
+
+/// Selects the fragment of text that is the most relevant to the query.
+fn best_extract<'a>(fragments: impl Iterator<Item = &'a str>, query_positive_terms: &[TermMatcher], lang: &str) -> Option<String> {
+    fn extract_score(extract: &str, query_positive_terms: &[TermMatcher], lang: &str) -> usize {
+        let mut score = 0;
+        let mut extract_words = tokenizer::tokenize(extract).into_iter().map(|w| {
+            let stemmed = tokenizer::stem(&w, lang);
+            (w, stemmed)
+        }).collect::<Vec<_>>();
+        if extract_words.is_empty() {
+            return 0;
+        }
+        let (first_word, first_stemmed) = extract_words.remove(0);
+        if term_position(query_positive_terms, &first_word, &first_stemmed).is_some() {
+            score += 4;
+        }
+        for query_positive_term in query_positive_terms {
+            if extract_words.iter().any(|(word, stemmed)| term_position(std::slice::from_ref(query_positive_term), word, stemmed).is_some()) {
+                score += 1;
+            }
+        }
+        score
+    }
+
+    let mut best_extract = "";
+    let mut best_extract_score = 0;
+    for fragment in fragments {
+        if fragment.len() >= 350 || fragment.len() <= 50 {
+            continue;
+        }
+        let score = extract_score(fragment, query_positive_terms, lang);
+        if score > best_extract_score {
+            best_extract_score = score;
+            best_extract = fragment;
+        }
+    }
+    match best_extract_score > 0 {
+        true => Some(best_extract.to_string()),
+        false => None,
+    }
+}
+
+/// Finds the first query term matching a word of a document.
+/// Exact terms are compared to the stemmed word, while prefixes and fuzzy terms may also match the word as written.
+fn term_position(query_positive_terms: &[TermMatcher], word: &str, stemmed: &str) -> Option<usize> {
+    query_positive_terms.iter().position(|matcher| match matcher {
+        TermMatcher::Exact(_) => matcher.matches(stemmed),
+        TermMatcher::Prefix(_) | TermMatcher::Fuzzy(..) => matcher.matches(stemmed) || matcher.matches(word),
+    })
+}
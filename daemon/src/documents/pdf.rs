@@ -0,0 +1,46 @@
+use pdf_extract::{Document, Object, PlainTextOutput};
+use super::*;
+
+/// PDF documents are titled after their metadata, or their first line of text.
+/// Their text is split in paragraphs on blank lines.
+pub(super) fn parse_pdf(raw: &[u8]) -> Option<TextDocument> {
+    let pdf = Document::load_mem(raw).ok()?;
+    if pdf.is_encrypted() {
+        return None;
+    }
+
+    // Extract text (pdf-extract panics on some malformed documents)
+    let mut text = String::new();
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        pdf_extract::output_doc(&pdf, &mut PlainTextOutput::new(&mut text))
+    })).ok()?.ok()?;
+    let mut document = TextDocument::default();
+    for paragraph in text.split("\n\n") {
+        document.push_paragraph(paragraph.to_string());
+    }
+
+    // Read metadata
+    let info = pdf.trailer.get(b"Info").ok()
+        .and_then(|info| pdf.dereference(info).ok())
+        .and_then(|(_, info)| info.as_dict().ok());
+    let get_text = |key: &[u8]| -> Option<String> {
+        let value = info?.get(key).ok()?;
+        let value = pdf_extract::decode_text_string(value).ok()?;
+        let value = value.trim();
+        match value.is_empty() {
+            true => None,
+            false => Some(value.to_string()),
+        }
+    };
+    document.title = get_text(b"Title").or_else(|| first_line(&text));
+    document.description = get_text(b"Subject");
+    document.lang = pdf.catalog().ok()
+        .and_then(|catalog| catalog.get(b"Lang").ok())
+        .and_then(|lang| match lang {
+            Object::String(lang, _) => Some(String::from_utf8_lossy(lang).to_lowercase()),
+            _ => None,
+        })
+        .and_then(|lang| lang.split('-').next().map(|l| l.to_string()));
+
+    Some(document)
+}
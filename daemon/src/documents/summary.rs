@@ -0,0 +1,129 @@
+use super::*;
+
+/// Everything needed to generate results for a document, computed once at indexing time.
+/// Results can then be generated for any query without fetching the document again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSummary {
+    pub lang: String,
+    pub title: Option<String>,
+    pub h1: Option<String>,
+    pub description: Option<String>,
+    pub favicons: Vec<FaviconDescriptor>,
+    /// Occurrences of each word of the document, as written, by tag
+    pub word_counts: HashMap<String, WordCount>,
+    pub word_count: WordCount,
+    /// Pieces of text that are candidates for extracts
+    pub fragments: Vec<String>,
+    pub common_words: Option<f64>,
+}
+
+impl DocumentSummary {
+    pub fn to_result(&self, query: &Query) -> Option<DocumentResult> {
+        if self.title.is_none() && self.h1.is_none() {
+            return None;
+        }
+
+        // Retrieve the most relevant extract
+        let query_positive_terms = query.positive_matchers();
+        let extract = best_extract(self.fragments.iter().map(|f| f.as_str()), &query_positive_terms, &self.lang);
+        if self.description.is_none() && extract.is_none() {
+            return None;
+        }
+
+        // Count terms
+        let mut term_counts = query_positive_terms.iter().map(|_| WordCount::default()).collect::<Vec<_>>();
+        for (word, word_count) in &self.word_counts {
+            let stemmed = tokenizer::stem(word, &self.lang);
+            if let Some(i) = term_position(&query_positive_terms, word, &stemmed) {
+                let term_count = term_counts.get_mut(i).expect("term_counts not initialized properly");
+                term_count.merge(word_count);
+            }
+        }
+
+        Some(DocumentResult {
+            cid: String::new(),
+            paths: Vec::new(),
+            favicons: self.favicons.clone(),
+            title: self.title.clone(),
+            h1: self.h1.clone(),
+            description: self.description.clone(),
+            extract,
+
+            structured_data: Vec::new(),
+
+            term_counts,
+            word_count: self.word_count.clone(),
+            common_words: self.common_words,
+        })
+    }
+}
+
+/// Collects the words of a document in a single pass, for both the index and the summary.
+pub(super) struct SummaryBuilder {
+    lang: String,
+    common_words: Option<&'static [&'static str]>,
+    common_words_bytes: usize,
+    uncommon_words_bytes: usize,
+    /// Stemmed words, in order, for the index
+    words: Vec<String>,
+    word_counts: HashMap<String, WordCount>,
+    word_count: WordCount,
+    fragments: Vec<String>,
+}
+
+impl SummaryBuilder {
+    pub fn new(lang: String) -> SummaryBuilder {
+        let common_words = match lang.as_str() {
+            "en" => Some(word_lists::WORDS_EN),
+            _ => None,
+        };
+        SummaryBuilder {
+            lang,
+            common_words,
+            common_words_bytes: 0,
+            uncommon_words_bytes: 0,
+            words: Vec::new(),
+            word_counts: HashMap::new(),
+            word_count: WordCount::default(),
+            fragments: Vec::new(),
+        }
+    }
+
+    /// Adds a word as written in the document.
+    /// The closure counts it according to its style.
+    pub fn add_word(&mut self, word: String, count: impl Fn(&mut WordCount)) {
+        if let Some(common_words) = self.common_words {
+            if common_words.sorted_contains(&word) {
+                self.common_words_bytes += word.len();
+            } else {
+                self.uncommon_words_bytes += word.len();
+            }
+        }
+        count(&mut self.word_count);
+        self.words.push(tokenizer::stem(&word, &self.lang));
+        count(self.word_counts.entry(word).or_default());
+    }
+
+    /// Keeps a piece of text if its size makes it a suitable extract.
+    pub fn add_fragment(&mut self, fragment: &str) {
+        if fragment.len() > 50 && fragment.len() < 350 {
+            self.fragments.push(fragment.to_string());
+        }
+    }
+
+    pub fn finish(self, title: Option<String>, h1: Option<String>, description: Option<String>, favicons: Vec<FaviconDescriptor>) -> (Vec<String>, DocumentSummary) {
+        let common_words = self.common_words.map(|_| self.common_words_bytes as f64 / (self.common_words_bytes + self.uncommon_words_bytes) as f64);
+        let summary = DocumentSummary {
+            lang: self.lang,
+            title,
+            h1,
+            description: description.filter(|d| !d.trim().is_empty()),
+            favicons,
+            word_counts: self.word_counts,
+            word_count: self.word_count,
+            fragments: self.fragments,
+            common_words,
+        };
+        (self.words, summary)
+    }
+}
@@ -0,0 +1,90 @@
+use super::*;
+
+/// Formatting of a span of text, as far as word counting is concerned.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct TextStyle {
+    /// Heading level, from 1 to 6
+    pub heading: Option<u8>,
+    pub strong: bool,
+    pub em: bool,
+    pub s: bool,
+}
+
+impl TextStyle {
+    fn add_to(&self, word_count: &mut WordCount) {
+        let h = |level| self.heading == Some(level);
+        word_count.add(h(1), h(2), h(3), h(4), h(5), h(6), self.strong, self.em, false, self.s)
+    }
+}
+
+/// Intermediate representation of documents that are not HTML.
+/// Each format is converted to this so that they can share the same inspection and result generation logic.
+#[derive(Debug, Default)]
+pub(super) struct TextDocument {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub lang: Option<String>,
+    /// All the text of the document, split in spans of the same style
+    pub spans: Vec<(String, TextStyle)>,
+    /// Blocks of text that are candidates for extracts
+    pub paragraphs: Vec<String>,
+}
+
+impl TextDocument {
+    /// Adds a paragraph of regular text.
+    pub fn push_paragraph(&mut self, paragraph: String) {
+        let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+        if paragraph.is_empty() {
+            return;
+        }
+        self.spans.push((paragraph.clone(), TextStyle::default()));
+        self.paragraphs.push(paragraph);
+    }
+}
+
+/// Plain text documents are split in paragraphs on blank lines.
+/// Their first line is used as a title.
+pub(super) fn parse_text(raw: &str) -> TextDocument {
+    let mut document = TextDocument::default();
+    for paragraph in raw.replace("\r\n", "\n").split("\n\n") {
+        document.push_paragraph(paragraph.to_string());
+    }
+    document.title = first_line(raw);
+    document
+}
+
+/// Returns the first non-empty line of the text if it is short enough to be a title.
+pub(super) fn first_line(raw: &str) -> Option<String> {
+    let line = raw.lines().map(|l| l.trim()).find(|l| !l.is_empty())?;
+    match line.len() <= 200 {
+        true => Some(line.to_string()),
+        false => None,
+    }
+}
+
+pub(super) fn inspect_text_document(document: TextDocument) -> Option<DocumentInspectionReport> {
+    let mut filters = HashMap::new();
+
+    let lang = document.lang.unwrap_or(String::from("unknown"));
+    let title = document.title.filter(|t| !t.trim().is_empty());
+
+    let mut summary = SummaryBuilder::new(lang.clone());
+    for (text, style) in &document.spans {
+        for word in tokenizer::tokenize(text) {
+            summary.add_word(word, |word_count| style.add_to(word_count));
+        }
+    }
+
+    // Long paragraphs are split in sentences to get extracts
+    for paragraph in &document.paragraphs {
+        match paragraph.len() >= 350 {
+            true => paragraph.split_inclusive(". ").for_each(|sentence| summary.add_fragment(sentence)),
+            false => summary.add_fragment(paragraph),
+        }
+    }
+
+    let (words, summary) = summary.finish(title, None, document.description, Vec::new());
+    filters.insert("lang", lang);
+
+    Some(DocumentInspectionReport { words, filters, summary })
+}
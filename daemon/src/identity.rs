@@ -0,0 +1,77 @@
+use crate::prelude::*;
+use libp2p_identity::DecodingError;
+
+#[derive(Debug)]
+pub enum IdentityError {
+    Io(IoError),
+    Decoding(DecodingError),
+}
+
+impl From<IoError> for IdentityError {
+    fn from(e: IoError) -> Self {
+        IdentityError::Io(e)
+    }
+}
+
+impl From<DecodingError> for IdentityError {
+    fn from(e: DecodingError) -> Self {
+        IdentityError::Decoding(e)
+    }
+}
+
+impl std::fmt::Display for IdentityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IdentityError::Io(e) => write!(f, "IoError: {e}"),
+            IdentityError::Decoding(e) => write!(f, "DecodingError: {e}"),
+        }
+    }
+}
+
+fn read_keypair(path: &str) -> Result<Keypair, IdentityError> {
+    let data = std::fs::read(path)?;
+    Ok(Keypair::from_protobuf_encoding(&data)?)
+}
+
+fn write_keypair(path: &str, keypair: &Keypair) -> Result<(), IdentityError> {
+    use std::io::Write;
+
+    let data = keypair.to_protobuf_encoding()?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    file.write_all(&data)?;
+    Ok(())
+}
+
+/// Loads the identity of our node from disk.
+/// The keypair is imported first if requested, and generated if none exists yet.
+pub fn load_keypair(config: &Args) -> Keypair {
+    if let Some(import_path) = &config.import_keypair {
+        let keypair = match read_keypair(import_path) {
+            Ok(keypair) => keypair,
+            Err(e) => panic!("Failed to import keypair from {import_path}: {e}"),
+        };
+        if let Err(e) = write_keypair(&config.keypair_path, &keypair) {
+            panic!("Failed to store imported keypair at {}: {e}", config.keypair_path);
+        }
+        info!("Imported keypair from {import_path}");
+        return keypair;
+    }
+
+    match read_keypair(&config.keypair_path) {
+        Ok(keypair) => keypair,
+        Err(IdentityError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            let keypair = Keypair::generate_ed25519();
+            if let Err(e) = write_keypair(&config.keypair_path, &keypair) {
+                panic!("Failed to store new keypair at {}: {e}", config.keypair_path);
+            }
+            info!("Generated new keypair at {}", config.keypair_path);
+            keypair
+        },
+        // We don't want to silently replace the identity of the node
+        Err(e) => panic!("Failed to load keypair from {}: {e}", config.keypair_path),
+    }
+}
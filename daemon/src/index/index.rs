@@ -46,6 +46,8 @@ impl DocumentIndex {
         let mut last_printed_error = None;
         let ipfs_rpc = &self.config.ipfs_rpc;
         let mut previous_load = -1.0;
+        let mut previous_pinned: Option<HashSet<String>> = None;
+        let mut gc_pending = false;
         loop {
             let mut to_list = Vec::new();
             let mut to_load = HashMap::new();
@@ -65,12 +67,17 @@ impl DocumentIndex {
                 }
             };
             last_printed_error = None;
-            to_list.extend(pinned.iter().filter_map(normalize_cid).filter(|cid| !listed.contains(cid)));
+            let pinned = pinned.iter().filter_map(normalize_cid).collect::<HashSet<_>>();
+            if previous_pinned.as_ref().map(|previous| previous.difference(&pinned).next().is_some()).unwrap_or(true) {
+                gc_pending = true;
+            }
+            to_list.extend(pinned.iter().filter(|cid| !listed.contains(*cid)).cloned());
             self.set_status(listed.len(), to_list.len(), loaded.len(), to_load.len(), to_load_unprioritized.len()).await;
 
             // Explore directories
             let start = Instant::now();
             if !to_list.is_empty() {debug!("{} elements to list", to_list.len())}
+            let mut listing_failed = false;
             while let Some(cid) = to_list.pop() {
                 if !listed.insert(cid.clone()) {continue}
                 self.set_status(listed.len(), to_list.len()+1, loaded.len(), to_load.len(), to_load_unprioritized.len()).await;
@@ -79,6 +86,7 @@ impl DocumentIndex {
                     Ok(new_links) => new_links,
                     Err(e) => {
                         warn!("Error listing potential directory: {e:?}");
+                        listing_failed = true;
                         continue;
                     },
                 };
@@ -89,8 +97,11 @@ impl DocumentIndex {
                         if !listed.contains(&child_cid) {
                             to_list.push(child_cid);
                         }
-                    } else if !loaded.contains(&child_cid) {
-                        match child_name.ends_with(".html") {
+                    } else if loaded.contains(&child_cid) {
+                        // Documents restored from the database still need to know where they are
+                        self.add_ancestor_if_indexed(&child_cid, child_name, &cid).await;
+                    } else {
+                        match DocumentFormat::from_name(&child_name).is_some() {
                             true => to_load.insert(child_cid, (child_name, cid.clone())),
                             false => to_load_unprioritized.insert(child_cid, (child_name, cid.clone())),
                         };
@@ -100,6 +111,21 @@ impl DocumentIndex {
                 to_list.dedup();
             }
 
+            // Forget documents that are no longer pinned
+            // Only complete listings tell what is reachable, so collection waits for one if listing failed
+            if gc_pending && !listing_failed {
+                let removed = self.remove_unreachable(&pinned).await;
+                if !removed.is_empty() {
+                    info!("Removed {} unpinned elements from index", removed.len());
+                }
+                for cid in removed {
+                    listed.remove(&cid);
+                    loaded.remove(&cid);
+                }
+                gc_pending = false;
+            }
+            previous_pinned = Some(pinned);
+
             // Load documents
             to_load_unprioritized.retain(|cid, _| !to_load.contains_key(cid));
             if !to_load.is_empty() {debug!("{} documents to load ({:.02?}s)", to_load.len(), start.elapsed().as_secs_f32())}
@@ -111,7 +137,7 @@ impl DocumentIndex {
 
                 loaded.insert(cid.clone());
                 let Ok(document) = fetch_document(ipfs_rpc, &cid).await else {continue};
-                let Some(inspected) = inspect_document(document) else {continue};
+                let Some(inspected) = inspect_document(document, Some(&name)) else {continue};
                 self.add_document(&cid, inspected).await;
                 self.add_ancestor(&cid, name, false, &parent_cid).await;
             }
@@ -156,6 +182,10 @@ impl DocumentIndex {
         self.inner.read().await.document_count()
     }
 
+    pub async fn folder_document_counts(&self, limit: usize) -> Vec<(String, u64)> {
+        self.inner.read().await.folder_document_counts(limit)
+    }
+
     pub async fn add_document(&self, cid: &String, doc: DocumentInspectionReport) {
         self.inner.write().await.add_document(cid, doc);
     }
@@ -164,6 +194,19 @@ impl DocumentIndex {
         self.inner.write().await.add_ancestor(cid, name, is_folder, folder_cid);
     }
 
+    pub async fn add_ancestor_if_indexed(&self, cid: &String, name: String, folder_cid: &String) {
+        let mut inner = self.inner.write().await;
+        if inner.cids.contains_right(cid) {
+            inner.add_ancestor(cid, name, false, folder_cid);
+        }
+    }
+
+    /// Removes everything that can't be reached from the pinned cids.
+    /// Returns the cids that were removed.
+    pub async fn remove_unreachable(&self, pinned: &HashSet<String>) -> Vec<String> {
+        self.inner.write().await.remove_unreachable(pinned).await
+    }
+
     pub async fn build_path(&self, cid: &String) -> Option<Vec<Vec<String>>> {
         self.inner.read().await.build_path(cid)
     }
@@ -171,6 +214,16 @@ impl DocumentIndex {
     pub async fn update_filter(&self) {
         self.inner.write().await.update_filter().await;
     }
+
+    /// The database is opened by the index, but also stores the known peers of the swarm.
+    #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
+    pub async fn peers_db(&self) -> DbPeersController {
+        self.inner.read().await.peers_db()
+    }
+
+    pub async fn fill_known(&self, query: &mut Query) {
+        query.fill_known(&self.inner.read().await.vocabulary);
+    }
 }
 
 
@@ -179,8 +232,8 @@ impl Store<FILTER_SIZE> for DocumentIndex {
     type Result = DocumentResult;
     type Query = Query;
 
-    fn hash_word(word: &str) -> Vec<usize>  {
-        let mut result = 1usize;
+    fn hash_word(word: &str, params: &FilterParams) -> Vec<usize>  {
+        let mut result = 1usize ^ params.seed as usize;
         const RANDOM_SEED: [usize; 16] = [542587211452, 5242354514, 245421154, 4534542154, 542866467, 545245414, 7867569786914, 88797854597, 24542187316, 645785447, 434963879, 4234274, 55418648642, 69454242114688, 74539841, 454214578213];
         for c in word.bytes() {
             for i in 0..8 {
@@ -188,7 +241,7 @@ impl Store<FILTER_SIZE> for DocumentIndex {
                 result = result.overflowing_add(c as usize + RANDOM_SEED[i*2+1]).0;
             }
         }
-        vec![result % (FILTER_SIZE * 8)]
+        params.bit_indices(result as u64)
     }
 
     async fn get_filter(&self) -> Filter<FILTER_SIZE> {
@@ -29,6 +29,68 @@ impl DocumentIndexInner {
         self.ancestors.entry(lcid).or_default().insert(ancestor_lcid, name);
     }
 
+    /// Lists the cids that can't be reached from the roots by following links from folders to their children.
+    pub(super) fn unreachable_cids(&self, roots: &HashSet<String>) -> Vec<LocalCid> {
+        let mut children: HashMap<LocalCid, Vec<LocalCid>> = HashMap::new();
+        for (child, ancestors) in &self.ancestors {
+            for ancestor in ancestors.keys() {
+                children.entry(*ancestor).or_default().push(*child);
+            }
+        }
+
+        let mut reachable = HashSet::new();
+        let mut to_visit = roots.iter().filter_map(|cid| self.cids.get_by_right(cid)).copied().collect::<Vec<_>>();
+        while let Some(lcid) = to_visit.pop() {
+            if reachable.insert(lcid) {
+                if let Some(children) = children.get(&lcid) {
+                    to_visit.extend(children);
+                }
+            }
+        }
+
+        self.cids.left_values().filter(|lcid| !reachable.contains(*lcid)).copied().collect()
+    }
+
+    /// Counts the documents each folder directly contains (non-recursive).
+    /// Only the `limit` largest folders are returned, largest first.
+    pub fn folder_document_counts(&self, limit: usize) -> Vec<(String, u64)> {
+        let mut counts: HashMap<LocalCid, u64> = HashMap::new();
+        for (child, ancestors) in &self.ancestors {
+            if self.folders.contains(child) {
+                continue;
+            }
+            for ancestor in ancestors.keys() {
+                *counts.entry(*ancestor).or_default() += 1;
+            }
+        }
+
+        let mut counts = counts
+            .into_iter()
+            .filter_map(|(lcid, count)| Some((self.cids.get_by_left(&lcid)?.to_owned(), count)))
+            .collect::<Vec<_>>();
+        counts.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+        counts.truncate(limit);
+        counts
+    }
+
+    /// Removes cids and their ancestry, returning the cids that were known.
+    /// Postings are left to the caller.
+    pub(super) fn forget_cids(&mut self, lcids: &HashSet<LocalCid>) -> Vec<String> {
+        let mut removed = Vec::new();
+        for lcid in lcids {
+            if let Some((_, cid)) = self.cids.remove_by_left(lcid) {
+                removed.push(cid);
+            }
+            self.folders.remove(lcid);
+            self.ancestors.remove(lcid);
+        }
+        for ancestors in self.ancestors.values_mut() {
+            ancestors.retain(|ancestor, _| !lcids.contains(ancestor));
+        }
+        self.ancestors.retain(|_, ancestors| !ancestors.is_empty());
+        removed
+    }
+
     pub fn build_path(&self, cid: &String) -> Option<Vec<Vec<String>>> {
         let lcid = match self.cids.get_by_right(cid) {
             Some(lcid) => lcid.to_owned(),
@@ -11,25 +11,32 @@ pub(super) struct DocumentIndexInner {
     pub(super) folders: HashSet<LocalCid>,
     pub(super) cids: BiHashMap<LocalCid, String>,
     cids_to_store: Vec<LocalCid>,
+    summaries_to_store: HashMap<LocalCid, DocumentSummary>,
 
     loaded_index: HashSet<String>,
     changed_index: HashSet<String>,
     in_use_index: HashMap<String, usize>,
-    in_memory_index: HashMap<String, HashMap<LocalCid, f32>>,
-    // todo filters
+    in_memory_index: HashMap<String, HashMap<LocalCid, Posting>>,
+    loaded_filters: HashSet<(String, String)>,
+    changed_filters: HashSet<(String, String)>,
+    in_memory_filters: HashMap<(String, String), Vec<LocalCid>>,
+    pub(super) vocabulary: Vocabulary,
 
     index_db: DbIndexController,
+    peers_db: DbPeersController,
 }
 
 impl DocumentIndexInner {
     pub async fn new(config: Arc<Args>) -> DocumentIndexInner {
         let (db, cid_counter, cids) = open_database(Arc::clone(&config));
-        let index_db = DbIndexController::from(db);
+        let index_db = DbIndexController::from(db.clone());
+        let peers_db = DbPeersController::from(db);
+        let filter = Filter::with_params(config.filter_params());
 
         let mut index = DocumentIndexInner {
             config,
 
-            filter: Filter::new(),
+            filter,
             filter_needs_update: !cids.is_empty(),
             
             cid_counter,
@@ -37,18 +44,39 @@ impl DocumentIndexInner {
             folders: HashSet::new(),
             cids,
             cids_to_store: Vec::new(),
+            summaries_to_store: HashMap::new(),
 
             loaded_index: HashSet::new(),
             changed_index: HashSet::new(),
             in_use_index: HashMap::new(),
             in_memory_index: HashMap::new(),
+            loaded_filters: HashSet::new(),
+            changed_filters: HashSet::new(),
+            in_memory_filters: HashMap::new(),
+            vocabulary: Vocabulary::default(),
 
             index_db,
+            peers_db,
         };
         index.update_filter().await;
         index
     }
 
+    pub fn peers_db(&self) -> DbPeersController {
+        self.peers_db.clone()
+    }
+
+    async fn load_vocabulary(&mut self) {
+        let start = Instant::now();
+        match self.index_db.compute_vocabulary().await {
+            Ok(vocabulary) => self.vocabulary = vocabulary,
+            Err(e) => error!("Failed to compute vocabulary: {e:?}"),
+        }
+        if self.vocabulary.len() > 0 {
+            trace!("Vocabulary of {} words loaded in {}ms", self.vocabulary.len(), start.elapsed().as_millis());
+        }
+    }
+
     // TODO: optimize
     async fn load_index_batch(&mut self, words: Vec<String>) {
         let new_data = self.index_db.get(words.into_iter().collect()).await.unwrap_or_default();
@@ -91,6 +119,35 @@ impl DocumentIndexInner {
         }
     }
 
+    async fn load_filters_batch(&mut self, keys: Vec<(String, String)>) {
+        let new_data = self.index_db.get_filters(keys).await.unwrap_or_default();
+        for (key, lcids) in new_data {
+            self.loaded_filters.insert(key.clone());
+            self.in_memory_filters.entry(key).or_default().extend(lcids.into_iter().filter(|lcid| self.cids.contains_left(lcid)));
+        }
+    }
+
+    /// Writes changed filters to the database and drops all filters from memory.
+    async fn unload_filters(&mut self) {
+        // Filters that changed without being loaded only contain the new documents
+        let to_load = self.changed_filters.iter()
+            .filter(|key| !self.loaded_filters.contains(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.load_filters_batch(to_load).await;
+
+        let items = self.changed_filters
+            .drain()
+            .filter_map(|key| self.in_memory_filters.remove(&key).map(|lcids| (key, lcids)))
+            .collect::<Vec<_>>();
+        self.in_memory_filters.clear();
+        self.loaded_filters.clear();
+        if let Err(e) = self.index_db.put_filters(items).await {
+            error!("Failed to unload filters: {e:?}");
+            // TODO handle error
+        }
+    }
+
     // TODO: optimize
     pub(super) async fn sweep(&mut self) {
         let start = Instant::now();
@@ -100,6 +157,7 @@ impl DocumentIndexInner {
         if count > 0 {
             trace!("Sweeped {count} words from index in {}ms", start.elapsed().as_millis());
         }
+        self.unload_filters().await;
 
         let cids = std::mem::take(&mut self.cids_to_store).into_iter().filter_map(|lcid| self.cids.get_by_left(&lcid).map(|cid| (lcid, cid.to_owned()))).collect::<Vec<_>>();
         let count = cids.len();
@@ -109,6 +167,15 @@ impl DocumentIndexInner {
         if count > 0 {
             trace!("Stored {count} cids in database");
         }
+
+        let summaries = std::mem::take(&mut self.summaries_to_store).into_iter().collect::<Vec<_>>();
+        let count = summaries.len();
+        if let Err(e) = self.index_db.put_summaries(summaries).await {
+            error!("Failed to store summaries: {e:?}")
+        }
+        if count > 0 {
+            trace!("Stored {count} summaries in database");
+        }
     }
 
     pub fn documents(&self) -> HashSet<String> {
@@ -135,53 +202,123 @@ impl DocumentIndexInner {
         self.cids.insert(lcid, cid.to_owned());
         self.cids_to_store.push(lcid);
         self.folders.remove(&lcid);
+        self.summaries_to_store.insert(lcid, doc.summary);
 
         // Index by words
         let word_count = doc.words.len() as f64;
-        for word in doc.words {
-            let frequencies = self.in_memory_index.entry(word.clone()).or_default();
-            *frequencies.entry(lcid).or_insert(0.) += 1. / word_count as f32;
+        for (position, word) in doc.words.into_iter().enumerate() {
+            let posting = self.in_memory_index.entry(word.clone()).or_default().entry(lcid).or_default();
+            posting.frequency += 1. / word_count as f32;
+            posting.positions.push(position as u32);
             self.changed_index.insert(word.clone());
             self.filter.add_word::<DocumentIndex>(&word);
+            self.vocabulary.insert(&word);
         }
         
         // Index by filters
-        /*for (key, value) in doc.filters {
-            self.filters.entry((key.to_string(), value.clone())).or_default().push(lcid);
-            self.filter.add_word::<DocumentIndex>(&format!("{key}={value}"));
-        }*/
+        for (key, value) in doc.filters {
+            let key = (key.to_string(), value);
+            self.filter.add_word::<DocumentIndex>(&format!("{}={}", key.0, key.1));
+            self.in_memory_filters.entry(key.clone()).or_default().push(lcid);
+            self.changed_filters.insert(key);
+        }
     }
 
     pub async fn update_filter(&mut self) {
+        self.vocabulary.rebuild();
         if !self.filter_needs_update {
             return;
         }
         let start = Instant::now();
-        self.filter = match self.index_db.compute_filter().await {
+        self.filter = match self.index_db.compute_filter(self.config.filter_params()).await {
             Ok(filter) => filter,
             Err(e) => {
                 error!("Failed to compute filter: {e:?}");
                 return;
             },
         };
+        self.load_vocabulary().await;
+
+        // Changes that haven't been written to the database yet
+        for word in self.changed_index.iter().filter(|word| self.in_memory_index.contains_key(*word)) {
+            self.filter.add_word::<DocumentIndex>(word);
+            self.vocabulary.insert(word);
+        }
+        for (key, value) in self.changed_filters.iter().filter(|key| self.in_memory_filters.contains_key(*key)) {
+            self.filter.add_word::<DocumentIndex>(&format!("{key}={value}"));
+        }
+        self.vocabulary.rebuild();
+
         self.filter_needs_update = false;
         trace!("Filter recomputed in {}ms", start.elapsed().as_millis());
     }
 
+    pub async fn remove_unreachable(&mut self, pinned: &HashSet<String>) -> Vec<String> {
+        let lcids = self.unreachable_cids(pinned).into_iter().collect::<HashSet<_>>();
+        if lcids.is_empty() {
+            return Vec::new();
+        }
+        let removed = self.forget_cids(&lcids);
+        self.cids_to_store.retain(|lcid| !lcids.contains(lcid));
+        self.summaries_to_store.retain(|lcid, _| !lcids.contains(lcid));
+
+        // Postings that haven't been written yet
+        self.in_memory_index.retain(|_, postings| {
+            postings.retain(|lcid, _| !lcids.contains(lcid));
+            !postings.is_empty()
+        });
+        self.in_memory_filters.retain(|_, filtered| {
+            filtered.retain(|lcid| !lcids.contains(lcid));
+            !filtered.is_empty()
+        });
+        self.changed_index.retain(|word| self.in_memory_index.contains_key(word));
+        self.loaded_index.retain(|word| self.in_memory_index.contains_key(word));
+        self.changed_filters.retain(|key| self.in_memory_filters.contains_key(key));
+        self.loaded_filters.retain(|key| self.in_memory_filters.contains_key(key));
+
+        // Postings on disk
+        if let Err(e) = self.index_db.remove_documents(lcids.into_iter().collect()).await {
+            error!("Failed to remove documents from database: {e:?}");
+        }
+
+        // Bloom filters can't forget words
+        self.filter_needs_update = true;
+
+        removed
+    }
+
     pub async fn search(&mut self, query: Arc<Query>) -> ResultStream<DocumentResult> {
-        let mut terms = query.terms();
+        let expanded = query.expand(&self.vocabulary);
+        let mut terms = expanded.terms();
         terms.sort();
         terms.dedup();
         terms.iter().for_each(|t| *self.in_use_index.entry((*t).to_owned()).or_default() += 1);
         self.load_index_batch(terms.iter().map(|t| (*t).to_owned()).collect()).await;
+        let filters = expanded.filters()
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .filter(|key| !self.loaded_filters.contains(key))
+            .collect::<Vec<_>>();
+        self.load_filters_batch(filters).await;
         
-        let matching_docs = match query.match_score(&self.filter) > 0 {
-            true => query.matching_docs(&self.in_memory_index, &HashMap::new()), // TODO
+        let matching_docs = match expanded.match_score(&self.filter) > 0 {
+            true => expanded.matching_docs(&self.in_memory_index, &self.in_memory_filters),
             false => Vec::new(),
         };
 
         terms.iter().for_each(|t| *self.in_use_index.entry((*t).to_owned()).or_default() -= 1);
 
+        // Summaries that haven't been stored yet are still in memory
+        let to_get = matching_docs.iter().filter(|lcid| !self.summaries_to_store.contains_key(*lcid)).copied().collect::<Vec<_>>();
+        let mut summaries = match to_get.is_empty() {
+            true => HashMap::new(),
+            false => self.index_db.get_summaries(to_get).await.unwrap_or_else(|e| {
+                error!("Failed to get summaries: {e:?}");
+                HashMap::new()
+            }),
+        };
+        summaries.extend(matching_docs.iter().filter_map(|lcid| self.summaries_to_store.get(lcid).map(|s| (*lcid, s.clone()))));
+
         let futures = matching_docs
             .into_iter()
             .filter_map(|lcid| {
@@ -189,12 +326,12 @@ impl DocumentIndexInner {
                 if cid.is_none() {
                     warn!("Found cid that is missing from cids field: {lcid:?}");
                 }
-                cid
+                cid.map(|cid| (lcid, cid))
             })
-            .map(|cid| (cid, self.build_path(cid).unwrap_or_default()))
-            .map(|(cid, paths)| cid_to_result_wrapper(Arc::clone(&query), cid.to_owned(), paths, Arc::clone(&self.config)))
+            .map(|(lcid, cid)| (lcid, cid, self.build_path(cid).unwrap_or_default()))
+            .map(|(lcid, cid, paths)| summary_to_result_wrapper(summaries.get(&lcid), Arc::clone(&query), cid.to_owned(), paths, Arc::clone(&self.config)))
             .collect();
 
-        Box::pin(DocumentResultStream { futures })
+        Box::pin(DocumentResultStream::new(futures, self.config.result_parallelism))
     }
 }
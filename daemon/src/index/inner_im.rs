@@ -11,15 +11,19 @@ pub(super) struct DocumentIndexInner {
     pub(super) folders: HashSet<LocalCid>,
     pub(super) cids: BiHashMap<LocalCid, String>,
 
-    index: HashMap<String, HashMap<LocalCid, f32>>,
+    index: HashMap<String, HashMap<LocalCid, Posting>>,
     filters: HashMap<(String, String), Vec<LocalCid>>,
+    pub(super) vocabulary: Vocabulary,
+    summaries: HashMap<LocalCid, DocumentSummary>,
 }
 
 impl DocumentIndexInner {
     pub async fn new(config: Arc<Args>) -> DocumentIndexInner {
+        let filter = Filter::with_params(config.filter_params());
+
         DocumentIndexInner {
             config,
-            filter: Filter::new(),
+            filter,
             filter_needs_update: false,
 
             ancestors: HashMap::new(),
@@ -29,7 +33,9 @@ impl DocumentIndexInner {
             cid_counter: 0,
 
             index: HashMap::new(),
-            filters: HashMap::new()
+            filters: HashMap::new(),
+            vocabulary: Vocabulary::default(),
+            summaries: HashMap::new(),
         }
     }   
     
@@ -49,13 +55,17 @@ impl DocumentIndexInner {
     }
 
     pub async fn update_filter(&mut self) {
+        self.vocabulary.rebuild();
         if !self.filter_needs_update {
             return;
         }
-        self.filter = Filter::new();
+        self.filter = Filter::with_params(self.config.filter_params());
         for word in self.index.keys() {
             self.filter.add_word::<DocumentIndex>(word);
         }
+        for (key, value) in self.filters.keys() {
+            self.filter.add_word::<DocumentIndex>(&format!("{key}={value}"));
+        }
         self.filter_needs_update = false;
     }
 
@@ -70,13 +80,16 @@ impl DocumentIndexInner {
         self.cid_counter += 1;
         self.cids.insert(lcid, cid.to_owned());
         self.folders.remove(&lcid);
+        self.summaries.insert(lcid, doc.summary);
 
         // Index by words
         let word_count = doc.words.len() as f64;
-        for word in doc.words {
-            let frequencies = self.index.entry(word.clone()).or_default();
-            *frequencies.entry(lcid).or_insert(0.) += 1. / word_count as f32;
+        for (position, word) in doc.words.into_iter().enumerate() {
+            let posting = self.index.entry(word.clone()).or_default().entry(lcid).or_default();
+            posting.frequency += 1. / word_count as f32;
+            posting.positions.push(position as u32);
             self.filter.add_word::<DocumentIndex>(&word);
+            self.vocabulary.insert(&word);
         }
         
         // Index by filters
@@ -86,20 +99,47 @@ impl DocumentIndexInner {
         }
     }
 
+    pub async fn remove_unreachable(&mut self, pinned: &HashSet<String>) -> Vec<String> {
+        let lcids = self.unreachable_cids(pinned).into_iter().collect::<HashSet<_>>();
+        if lcids.is_empty() {
+            return Vec::new();
+        }
+        let removed = self.forget_cids(&lcids);
+        self.summaries.retain(|lcid, _| !lcids.contains(lcid));
+
+        self.index.retain(|_, postings| {
+            postings.retain(|lcid, _| !lcids.contains(lcid));
+            !postings.is_empty()
+        });
+        self.filters.retain(|_, filtered| {
+            filtered.retain(|lcid| !lcids.contains(lcid));
+            !filtered.is_empty()
+        });
+
+        // Neither Bloom filters nor the vocabulary can forget words
+        let mut words = self.index.keys().collect::<Vec<_>>();
+        words.sort();
+        self.vocabulary = Vocabulary::from_sorted(words);
+        self.filter_needs_update = true;
+
+        removed
+    }
+
     // TODO: switching self to static may improve performance by a lot
     pub async fn search(&self, query: Arc<Query>) -> ResultStream<DocumentResult> {
-        let matching_docs = match query.match_score(&self.filter) > 0 {
-            true => query.matching_docs(&self.index, &self.filters),
+        let expanded = query.expand(&self.vocabulary);
+        let matching_docs = match expanded.match_score(&self.filter) > 0 {
+            true => expanded.matching_docs(&self.index, &self.filters),
             false => Vec::new(),
         };
 
         let futures = matching_docs
             .into_iter()
-            .filter_map(|lcid| self.cids.get_by_left(&lcid))
-            .map(|cid| (cid, self.build_path(cid).unwrap_or_default()))
-            .map(|(cid, paths)| cid_to_result_wrapper(Arc::clone(&query), cid.to_owned(), paths, Arc::clone(&self.config)))
+            .filter_map(|lcid| self.cids.get_by_left(&lcid).map(|cid| (lcid, cid)))
+            .map(|(lcid, cid)| (lcid, cid, self.build_path(cid).unwrap_or_default()))
+            .map(|(lcid, cid, paths)| summary_to_result_wrapper(self.summaries.get(&lcid), Arc::clone(&query), cid.to_owned(), paths, Arc::clone(&self.config)))
             .collect();
 
-        Box::pin(DocumentResultStream { futures })
+        Box::pin(DocumentResultStream::new(futures, self.config.result_parallelism))
     }
 }
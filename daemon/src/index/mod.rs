@@ -6,12 +6,16 @@ use crate::prelude::*;
 
 const REFRESH_INTERVAL: u64 = 100;
 const SWEEP_INTERVAL: u64 = 30;
+/// Time after which a document that has to be fetched is given up, in seconds
+const RESULT_TIMEOUT: u64 = 10;
 
 mod index;
 mod status;
 mod inner_common;
+mod vocabulary;
 pub use index::*;
 pub use status::*;
+pub use vocabulary::*;
 
 #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
 mod inner_db;
@@ -40,38 +44,81 @@ impl Hash for LocalDid {
     }
 }
 
+/// Occurrences of a word in a document
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Posting {
+    /// Share of the words of the document that are this word
+    pub frequency: f32,
+    /// Positions of the word in the document, in ascending order
+    pub positions: Vec<u32>,
+}
+
+type ResultFuture = Pin<Box<dyn Future<Output = Option<DocumentResult>> + Send>>;
+
 pub async fn cid_to_result(query: Arc<Query>, cid: String, paths: Vec<Vec<String>>, config: Arc<Args>) -> Option<DocumentResult> {
     let Ok(raw) = fetch_document(&config.ipfs_rpc, &cid).await else {return None};
     generate_result(raw, cid, &query, paths)
 }
 
-fn cid_to_result_wrapper(query: Arc<Query>, cid: String, paths: Vec<Vec<String>>, config: Arc<Args>) -> Pin<Box<dyn Future<Output = Option<DocumentResult>> + Send>> {
-    Box::pin(cid_to_result(query, cid, paths, config))
+fn cid_to_result_wrapper(query: Arc<Query>, cid: String, paths: Vec<Vec<String>>, config: Arc<Args>) -> ResultFuture {
+    Box::pin(async move {
+        match timeout(Duration::from_secs(RESULT_TIMEOUT), cid_to_result(query, cid.clone(), paths, config)).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!("Timed out generating result for {cid}");
+                None
+            },
+        }
+    })
+}
+
+/// Generates the result from the summary stored at indexing time, falling back to fetching the document.
+fn summary_to_result_wrapper(summary: Option<&DocumentSummary>, query: Arc<Query>, cid: String, paths: Vec<Vec<String>>, config: Arc<Args>) -> ResultFuture {
+    let Some(summary) = summary else {
+        return cid_to_result_wrapper(query, cid, paths, config);
+    };
+    let result = summary.to_result(&query).map(|mut result| {
+        result.cid = cid;
+        result.paths = paths;
+        result
+    });
+    Box::pin(futures::future::ready(result))
 }
 
+/// Generates results concurrently, up to a limit.
+/// Futures are started in the order they are given, which is the relevance order, and results are yielded as soon as they are ready.
 struct DocumentResultStream {
-    futures: Vec<Pin<Box<dyn Future<Output = Option<DocumentResult>> + Send>>>,
+    pending: std::collections::VecDeque<ResultFuture>,
+    running: FuturesUnordered<ResultFuture>,
+    parallelism: usize,
+}
+
+impl DocumentResultStream {
+    fn new(futures: Vec<ResultFuture>, parallelism: usize) -> DocumentResultStream {
+        DocumentResultStream {
+            pending: futures.into(),
+            running: FuturesUnordered::new(),
+            parallelism: parallelism.max(1),
+        }
+    }
 }
 
 impl Stream for DocumentResultStream {
     type Item = DocumentResult;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Option<Self::Item>> {
-        match self.futures.last_mut() {
-            Some(fut) => {
-                match fut.as_mut().poll(cx) {
-                    std::task::Poll::Ready(Some(r)) => {
-                        self.futures.pop();
-                        std::task::Poll::Ready(Some(r))
-                    },
-                    std::task::Poll::Ready(None) => {
-                        self.futures.pop();
-                        self.poll_next(cx)
-                    },
-                    std::task::Poll::Pending => std::task::Poll::Pending,
-                }
-            },
-            None => std::task::Poll::Ready(None),
+        loop {
+            while self.running.len() < self.parallelism {
+                let Some(fut) = self.pending.pop_front() else {break};
+                self.running.push(fut);
+            }
+
+            match self.running.poll_next_unpin(cx) {
+                std::task::Poll::Ready(Some(Some(r))) => return std::task::Poll::Ready(Some(r)),
+                std::task::Poll::Ready(Some(None)) => continue,
+                std::task::Poll::Ready(None) => return std::task::Poll::Ready(None),
+                std::task::Poll::Pending => return std::task::Poll::Pending,
+            }
         }
     }
 }
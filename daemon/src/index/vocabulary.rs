@@ -0,0 +1,96 @@
+use super::*;
+use fst::{automaton::{Levenshtein, Str}, Automaton, IntoStreamer, Set, SetBuilder, Streamer};
+use std::collections::BTreeSet;
+
+/// Maximum number of indexed words a prefix or fuzzy term can expand to
+const MAX_EXPANSIONS: usize = 32;
+
+/// Sorted set of all the words of the index, used to expand prefix and fuzzy terms.
+/// Words are added to a pending set, and merged into the FST when the filter is updated.
+#[derive(Default)]
+pub struct Vocabulary {
+    words: Set<Vec<u8>>,
+    pending: BTreeSet<String>,
+}
+
+impl Vocabulary {
+    /// Builds the vocabulary from words that are already sorted, like the keys of the database.
+    pub fn from_sorted<I: IntoIterator<Item = S>, S: AsRef<[u8]>>(words: I) -> Vocabulary {
+        let words = Set::from_iter(words).unwrap_or_else(|e| {
+            error!("Failed to build vocabulary: {e}");
+            Set::default()
+        });
+        Vocabulary { words, pending: BTreeSet::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len() + self.pending.len()
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        if !self.words.contains(word) {
+            self.pending.insert(word.to_owned());
+        }
+    }
+
+    /// Merges pending words into the FST.
+    pub fn rebuild(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let mut builder = SetBuilder::memory();
+        let pending_set = match Set::from_iter(&pending) {
+            Ok(pending_set) => pending_set,
+            Err(e) => {
+                error!("Failed to rebuild vocabulary: {e}");
+                self.pending = pending;
+                return;
+            },
+        };
+        let mut union = self.words.op().add(&pending_set).union();
+        while let Some(word) = union.next() {
+            if let Err(e) = builder.insert(word) {
+                error!("Failed to rebuild vocabulary: {e}");
+                drop(union);
+                self.pending = pending;
+                return;
+            }
+        }
+        drop(union);
+        self.words = builder.into_set();
+    }
+
+    fn search(&self, automaton: impl Automaton, matches: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut stream = self.words.search(automaton).into_stream();
+        while let Some(word) = stream.next() {
+            if words.len() >= MAX_EXPANSIONS {
+                return words;
+            }
+            if let Ok(word) = std::str::from_utf8(word) {
+                words.push(word.to_owned());
+            }
+        }
+        words.extend(self.pending.iter().filter(|w| matches(w)).take(MAX_EXPANSIONS - words.len()).cloned());
+        words
+    }
+
+    /// Lists known words matching a term, up to [MAX_EXPANSIONS].
+    pub fn expand(&self, matcher: TermMatcher) -> Vec<String> {
+        match matcher {
+            TermMatcher::Exact(word) => match self.words.contains(word) || self.pending.contains(word) {
+                true => vec![word.to_owned()],
+                false => Vec::new(),
+            },
+            TermMatcher::Prefix(prefix) => self.search(Str::new(prefix).starts_with(), |w| matcher.matches(w)),
+            TermMatcher::Fuzzy(word, distance) => match Levenshtein::new(word, distance as u32) {
+                Ok(automaton) => self.search(automaton, |w| matcher.matches(w)),
+                Err(e) => {
+                    warn!("Failed to build automaton for {word:?}: {e}");
+                    Vec::new()
+                },
+            },
+        }
+    }
+}
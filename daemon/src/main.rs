@@ -14,6 +14,8 @@ mod clap;
 mod swarm;
 mod query;
 mod dns_pins;
+mod identity;
+mod metrics;
 
 #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
 mod database;
@@ -42,7 +44,10 @@ async fn main() {
     let (node, keypair) = Node::init(Arc::clone(&config), index.clone()).await;
     let node = node.run();
     
-    let search_park = Arc::new(SearchPark::new());
+    let peer_store = PeerStore::new(&config, &index).await;
+    node.sw.load_known_peers(&peer_store).await;
+
+    let search_park = Arc::new(SearchPark::new(Arc::clone(&node.sw), Arc::clone(&config)));
 
     let f1 = serve_api(Arc::clone(&config), index.clone(), search_park, node.clone());
     let f2 = update_census_task(node.clone(), index.clone(), keypair.clone(), Arc::clone(&config));
@@ -50,5 +55,6 @@ async fn main() {
     let f4 = cleanup_db_task(node.clone());
     let f5 = manage_dns_pins(Arc::clone(&config));
     let f6 = index.run();
-    tokio::join!(f1, f2, f3, f4, f5, f6);
+    let f7 = save_known_peers_task(node.clone(), peer_store);
+    tokio::join!(f1, f2, f3, f4, f5, f6, f7);
 }
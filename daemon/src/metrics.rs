@@ -0,0 +1,116 @@
+use crate::prelude::*;
+use std::{fmt::Write, sync::atomic::{AtomicU64, Ordering::Relaxed}};
+
+/// Upper bounds of the remote search duration buckets (in seconds)
+const DURATION_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+pub static METRICS: Metrics = Metrics::new();
+
+/// Metrics that are recorded as events happen.
+/// Everything else is read from the rest of the daemon when metrics are rendered.
+pub struct Metrics {
+    remote_searches: AtomicU64,
+    remote_search_results: AtomicU64,
+    remote_search_duration_buckets: [AtomicU64; DURATION_BUCKETS.len()],
+    remote_search_duration_sum_us: AtomicU64,
+    ipfs_rpc_reqwest_errors: AtomicU64,
+    ipfs_rpc_json_errors: AtomicU64,
+    ipfs_rpc_invalid_response_errors: AtomicU64,
+}
+
+impl Metrics {
+    const fn new() -> Metrics {
+        Metrics {
+            remote_searches: AtomicU64::new(0),
+            remote_search_results: AtomicU64::new(0),
+            remote_search_duration_buckets: [const { AtomicU64::new(0) }; DURATION_BUCKETS.len()],
+            remote_search_duration_sum_us: AtomicU64::new(0),
+            ipfs_rpc_reqwest_errors: AtomicU64::new(0),
+            ipfs_rpc_json_errors: AtomicU64::new(0),
+            ipfs_rpc_invalid_response_errors: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record_remote_search(&self, results: usize, duration: Duration) {
+        self.remote_searches.fetch_add(1, Relaxed);
+        self.remote_search_results.fetch_add(results as u64, Relaxed);
+        self.remote_search_duration_sum_us.fetch_add(duration.as_micros() as u64, Relaxed);
+        let seconds = duration.as_secs_f64();
+        if let Some(i) = DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.remote_search_duration_buckets[i].fetch_add(1, Relaxed);
+        }
+    }
+
+    pub fn record_ipfs_rpc_error(&self, error: &IpfsRpcError) {
+        let counter = match error {
+            IpfsRpcError::Reqwest(_) => &self.ipfs_rpc_reqwest_errors,
+            IpfsRpcError::Json(_) => &self.ipfs_rpc_json_errors,
+            IpfsRpcError::InvalidResponse(_) => &self.ipfs_rpc_invalid_response_errors,
+        };
+        counter.fetch_add(1, Relaxed);
+    }
+}
+
+/// Renders all metrics in the OpenMetrics text format.
+pub async fn render_metrics(index: &DocumentIndex, node: &NodeController, search_park: &SearchPark) -> String {
+    let status = index.status().await;
+    let document_count = index.document_count().await;
+    let filter_load = index.get_filter().await.load();
+    let (seeders, leechers) = node.kamilata_counts().await;
+    let (first_class, second_class, transient) = node.sw.class_counts().await;
+    let active_searches = search_park.active_searches().await;
+
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: &[(&str, f64)]| {
+        let _ = writeln!(out, "# TYPE {name} gauge");
+        let _ = writeln!(out, "# HELP {name} {help}");
+        for (labels, value) in samples {
+            let _ = writeln!(out, "{name}{labels} {value}");
+        }
+    };
+    gauge("admarus_indexing_listed", "Folders listed", &[("", status.listed as f64)]);
+    gauge("admarus_indexing_to_list", "Folders waiting to be listed", &[("", status.to_list as f64)]);
+    gauge("admarus_indexing_loaded", "Documents loaded", &[("", status.loaded as f64)]);
+    gauge("admarus_indexing_to_load", "Documents waiting to be loaded", &[("", status.to_load as f64)]);
+    gauge("admarus_indexing_to_load_unprioritized", "Unprioritized documents waiting to be loaded", &[("", status.to_load_unprioritized as f64)]);
+    gauge("admarus_indexing_updating_filter", "Whether the filter is being updated", &[("", status.updating_filter as u8 as f64)]);
+    gauge("admarus_documents", "Documents in the index", &[("", document_count as f64)]);
+    gauge("admarus_filter_load", "Proportion of bits set in the Bloom filter", &[("", filter_load)]);
+    gauge("admarus_kamilata_seeders", "Peers we leech filters from", &[("", seeders as f64)]);
+    gauge("admarus_kamilata_leechers", "Peers we seed filters to", &[("", leechers as f64)]);
+    gauge("admarus_peers", "Connected peers by class", &[
+        ("{class=\"first\"}", first_class as f64),
+        ("{class=\"second\"}", second_class as f64),
+        ("{class=\"transient\"}", transient as f64),
+    ]);
+    gauge("admarus_active_searches", "Searches started through the API that are still tracked", &[("", active_searches as f64)]);
+
+    let m = &METRICS;
+    let _ = writeln!(out, "# TYPE admarus_remote_searches counter");
+    let _ = writeln!(out, "# HELP admarus_remote_searches Search requests served to peers");
+    let _ = writeln!(out, "admarus_remote_searches_total {}", m.remote_searches.load(Relaxed));
+    let _ = writeln!(out, "# TYPE admarus_remote_search_results counter");
+    let _ = writeln!(out, "# HELP admarus_remote_search_results Results sent to peers");
+    let _ = writeln!(out, "admarus_remote_search_results_total {}", m.remote_search_results.load(Relaxed));
+
+    let _ = writeln!(out, "# TYPE admarus_remote_search_duration_seconds histogram");
+    let _ = writeln!(out, "# HELP admarus_remote_search_duration_seconds Time spent serving search requests to peers");
+    let mut cumulative = 0;
+    for (bound, count) in DURATION_BUCKETS.iter().zip(&m.remote_search_duration_buckets) {
+        cumulative += count.load(Relaxed);
+        let _ = writeln!(out, "admarus_remote_search_duration_seconds_bucket{{le=\"{bound}\"}} {cumulative}");
+    }
+    let count = m.remote_searches.load(Relaxed);
+    let _ = writeln!(out, "admarus_remote_search_duration_seconds_bucket{{le=\"+Inf\"}} {count}");
+    let _ = writeln!(out, "admarus_remote_search_duration_seconds_sum {}", m.remote_search_duration_sum_us.load(Relaxed) as f64 / 1_000_000.0);
+    let _ = writeln!(out, "admarus_remote_search_duration_seconds_count {count}");
+
+    let _ = writeln!(out, "# TYPE admarus_ipfs_rpc_errors counter");
+    let _ = writeln!(out, "# HELP admarus_ipfs_rpc_errors Failed calls to the IPFS RPC by error kind");
+    let _ = writeln!(out, "admarus_ipfs_rpc_errors_total{{kind=\"reqwest\"}} {}", m.ipfs_rpc_reqwest_errors.load(Relaxed));
+    let _ = writeln!(out, "admarus_ipfs_rpc_errors_total{{kind=\"json\"}} {}", m.ipfs_rpc_json_errors.load(Relaxed));
+    let _ = writeln!(out, "admarus_ipfs_rpc_errors_total{{kind=\"invalid_response\"}} {}", m.ipfs_rpc_invalid_response_errors.load(Relaxed));
+
+    out.push_str("# EOF\n");
+    out
+}
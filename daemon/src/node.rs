@@ -1,6 +1,16 @@
 use crate::prelude::*;
 
-pub const FILTER_SIZE: usize = 125000;
+/// Largest Bloom filters we handle (in bytes).
+/// The size of our own filter is configured separately.
+pub const FILTER_SIZE: usize = 500_000;
+
+/// Protocol name used by Kamilata, also used to find Admarus nodes through discovery
+pub const KAMILATA_PROTOCOL: &str = "/admarus/kamilata/0.2.0";
+
+/// Seconds after which a leecher we refused may try again
+const REFUSED_LEECHER_RETRY_DELAY: u32 = 10*60;
+/// Seconds after which an evicted leecher may try again
+const EVICTED_LEECHER_RETRY_DELAY: u32 = 3600;
 
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "Event")]
@@ -38,11 +48,13 @@ impl From<DiscoveryEvent> for Event {
 pub struct Node {
     swarm: Swarm<AdmarusBehaviour>,
     sw: Arc<SwarmManager>,
+    /// Used to sign the record we give to discovery peers
+    keypair: Keypair,
 }
 
 impl Node {
     pub async fn init(config: Arc<Args>, index: DocumentIndex) -> (Node, Keypair) {
-        let keypair = Keypair::generate_ed25519();
+        let keypair = load_keypair(&config);
         let peer_id = PeerId::from(keypair.public());
         info!("Local peer id: {peer_id}");
 
@@ -57,11 +69,11 @@ impl Node {
 
         let kamilata = KamilataBehaviour::new_with_config_and_store(peer_id, KamilataConfig {
             approve_leecher: Some(Box::new(approve_leecher)),
-            protocol_names: vec![String::from("/admarus/kamilata/0.1.0")],
+            protocol_names: vec![String::from(KAMILATA_PROTOCOL)],
             ..KamilataConfig::default()
         }, index);
         let identify = IdentifyBehaviour::new(
-            IdentifyConfig::new(String::from("admarus/0.1.0"), keypair.public())
+            IdentifyConfig::new(String::from("admarus/0.2.0"), keypair.public())
         );
         let discovery = DiscoveryBehavior::new_with_config(DiscoveryConfig {
             default_visibility: true,
@@ -98,6 +110,7 @@ impl Node {
         (Node {
             swarm,
             sw: swarm_manager,
+            keypair: keypair.clone(),
         }, keypair)
     }
 
@@ -109,6 +122,21 @@ impl Node {
         &mut self.swarm.behaviour_mut().discovery
     }
 
+    /// Signs a new discovery record with our current addresses.
+    async fn update_discovery_record(&mut self) {
+        let mut addrs = self.swarm.external_addresses().cloned().collect::<Vec<_>>();
+        for addr in self.swarm.listeners() {
+            if !addrs.contains(addr) {
+                addrs.push(addr.clone());
+            }
+        }
+        let keypair = self.keypair.clone();
+        let r = self.disc_mut().set_local_record(&keypair, addrs, vec![String::from(KAMILATA_PROTOCOL)]).await;
+        if let Err(e) = r {
+            error!("Error while signing discovery record: {e}");
+        }
+    }
+
     pub fn run(mut self) -> NodeController {
         let (sender, mut receiver) = channel(1);
         let controller = NodeController {
@@ -131,6 +159,11 @@ impl Node {
                             let addrs = self.swarm.external_addresses();
                             let _ = sender.send(addrs.cloned().collect());
                         },
+                        ClientCommand::GetKamilataCounts { sender } => {
+                            let seeders = self.kam_mut().seeder_count().await;
+                            let leechers = self.kam_mut().leecher_count().await;
+                            let _ = sender.send((seeders, leechers));
+                        },
                         ClientCommand::QueryPeers { query: q, sender } => {
                             self.disc_mut().start_query(q, sender);
                         }
@@ -141,6 +174,7 @@ impl Node {
                             }
                         },
                         ClientCommand::Disconnect { peer_id } => {
+                            self.sw.on_disconnect_requested(&peer_id).await;
                             let r = self.swarm.disconnect_peer_id(peer_id);
                             if let Err(e) = r {
                                 error!("Error while disconnecting from {peer_id}: {e:?}");
@@ -172,12 +206,19 @@ impl Node {
                         SwarmEvent::Behaviour(Event::Kamilata(event)) => match event {
                             KamilataEvent::LeecherAdded { peer_id, filter_count, interval_ms } => {
                                 debug!("Leecher added: {peer_id} (filter_count: {filter_count}, interval_ms: {interval_ms})");
-                                let r = self.sw.on_leecher_added(peer_id).await;
-                                if let Err(e) = r {
-                                    error!("Error while adding leecher {peer_id}: {e:?}");
-                                    self.kam_mut().stop_seeding(peer_id);
-                                } else if self.sw.class(&peer_id).await == Some(PeerClass::Second) {
-                                    self.kam_mut().leech_from(peer_id);
+                                match self.sw.on_leecher_added(peer_id).await {
+                                    Some(evicted) if evicted == peer_id => {
+                                        debug!("Too many leechers, refusing {peer_id}");
+                                        self.kam_mut().evict_leecher(peer_id, "Too many leechers", Some(REFUSED_LEECHER_RETRY_DELAY));
+                                    },
+                                    Some(evicted) => {
+                                        debug!("Too many leechers, evicting {evicted} in favor of {peer_id}");
+                                        self.kam_mut().evict_leecher(evicted, "Evicted in favor of a better peer", Some(EVICTED_LEECHER_RETRY_DELAY));
+                                        self.kam_mut().leech_from(peer_id);
+                                    },
+                                    None => if self.sw.class(&peer_id).await == Some(PeerClass::Second) {
+                                        self.kam_mut().leech_from(peer_id);
+                                    },
                                 }
                             },
                             KamilataEvent::SeederAdded { peer_id } => {
@@ -195,13 +236,43 @@ impl Node {
                                 debug!("Seeder removed: {peer_id}");
                                 self.sw.on_seeder_removed(&peer_id).await;
                             },
+                            KamilataEvent::FiltersReceived { peer_id } => {
+                                self.sw.on_filters_received(&peer_id).await;
+                            },
+                            KamilataEvent::SearchServed { peer_id, results, duration } => {
+                                trace!("Served {results} results to {peer_id} in {}ms", duration.as_millis());
+                                METRICS.record_remote_search(results, duration);
+                                self.sw.record(&peer_id, ReputationEvent::Queried).await;
+                            },
+                            KamilataEvent::Disconnected { peer_id, reason, try_again_in } => {
+                                debug!("{peer_id} stopped seeding to us: {reason} (try again in {try_again_in:?})");
+                                self.sw.on_kamilata_disconnect(&peer_id, try_again_in).await;
+                            },
                         },
                         // Discovery events
                         SwarmEvent::Behaviour(Event::Discovery(event)) => match event {
-
-                        }
-                        SwarmEvent::NewListenAddr { listener_id, address } => debug!("Listening on {address} (listener id: {listener_id:?})"),
+                            DiscoveryEvent::PeersDiscovered { from, mut peers } => {
+                                peers.remove(self.swarm.local_peer_id());
+                                self.sw.on_peers_discovered(from, peers).await;
+                            },
+                            DiscoveryEvent::PeersRequested { peer_id } => {
+                                self.sw.record(&peer_id, ReputationEvent::AskedPeers).await;
+                            },
+                        },
+                        SwarmEvent::NewListenAddr { listener_id, address } => {
+                            debug!("Listening on {address} (listener id: {listener_id:?})");
+                            self.update_discovery_record().await;
+                        },
+                        SwarmEvent::ExternalAddrConfirmed { address } => {
+                            debug!("External address confirmed: {address}");
+                            self.update_discovery_record().await;
+                        },
                         SwarmEvent::ConnectionEstablished { peer_id, endpoint, num_established, .. } => {
+                            if self.sw.is_banned(&peer_id).await {
+                                debug!("Refusing connection from banned peer {peer_id}");
+                                let _ = self.swarm.disconnect_peer_id(peer_id);
+                                continue;
+                            }
                             debug!("Connection established with {peer_id} (num_established: {num_established}, endpoint: {endpoint:?})");
                             self.sw.on_peer_connected(peer_id).await;
                         },
@@ -212,7 +283,10 @@ impl Node {
                             }
                         },
                         SwarmEvent::OutgoingConnectionError { peer_id, connection_id, error } => debug!("Outgoing connection {connection_id:?} error to {peer_id:?}: {error}"),
-                        SwarmEvent::ExpiredListenAddr { listener_id, address } => debug!("Expired listen addr {address} (listener id: {listener_id:?})"),
+                        SwarmEvent::ExpiredListenAddr { listener_id, address } => {
+                            debug!("Expired listen addr {address} (listener id: {listener_id:?})");
+                            self.update_discovery_record().await;
+                        },
                         SwarmEvent::ListenerClosed { listener_id, addresses, reason } => debug!("Listener closed (listener id: {listener_id:?}, addresses: {addresses:?}, reason: {reason:?})"),
                         SwarmEvent::ListenerError { listener_id, error } => debug!("Listener error (listener id: {listener_id:?}, error: {error})"),
                         SwarmEvent::Dialing { peer_id, connection_id } => debug!("Dialing {peer_id:?} ({connection_id:?})"),
@@ -236,6 +310,9 @@ enum ClientCommand {
     GetExternalAddrs {
         sender: OneshotSender<Vec<Multiaddr>>,
     },
+    GetKamilataCounts {
+        sender: OneshotSender<(usize, usize)>,
+    },
     QueryPeers {
         query: PeerListQuery,  
         sender: OneshotSender<Result<DiscoveryResponse, IoError>>,
@@ -259,11 +336,11 @@ impl NodeController {
         self.peer_id
     }
 
-    pub async fn search(&self, query: Query) -> OngoingSearchController<FILTER_SIZE, DocumentIndex> {
+    pub async fn search(&self, query: Query, config: SearchConfig) -> OngoingSearchController<FILTER_SIZE, DocumentIndex> {
         let (sender, receiver) = oneshot_channel();
         let _ = self.sender.send(ClientCommand::Search {
             query,
-            config: SearchConfig::default(),
+            config,
             sender,
         }).await;
         receiver.await.expect("Channel closed")
@@ -277,6 +354,15 @@ impl NodeController {
         receiver.await.expect("Channel closed")
     }
 
+    /// Returns the number of peers we leech from and seed to.
+    pub async fn kamilata_counts(&self) -> (usize, usize) {
+        let (sender, receiver) = oneshot_channel();
+        let _ = self.sender.send(ClientCommand::GetKamilataCounts {
+            sender,
+        }).await;
+        receiver.await.expect("Channel closed")
+    }
+
     pub async fn dial_with_peer_id(&self, peer_id: PeerId, addrs: Vec<Multiaddr>) {
         let _ = self.sender.send(ClientCommand::Dial(
             DialOpts::peer_id(peer_id).condition(libp2p::swarm::dial_opts::PeerCondition::Disconnected)
@@ -9,12 +9,14 @@ pub use crate::{
     clap::*,
     swarm::*,
     dns_pins::*,
+    identity::*,
     query::*,
+    metrics::*,
 };
 #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
 pub use crate::database::*;
 
-pub use discovery_protocol::{Behaviour as DiscoveryBehavior, Event as DiscoveryEvent, Config as DiscoveryConfig, Response as DiscoveryResponse, PeerListQuery};
+pub use discovery_protocol::{Behaviour as DiscoveryBehavior, Event as DiscoveryEvent, Config as DiscoveryConfig, Response as DiscoveryResponse, Info as DiscoveryInfo, DiscoveredPeer, PeerListQuery};
 pub use clap::Parser;
 pub use log::{info, warn, error, debug, trace};
 pub use kamilata::{prelude::*, db::TooManyLeechers, behaviour::KamilataEvent, store::{ResultStream, ResultStreamBuilderFut}};
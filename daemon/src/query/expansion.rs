@@ -0,0 +1,62 @@
+use crate::prelude::*;
+
+impl QueryComp {
+    /// Documents are indexed with stemmed words, so fuzzy terms are also looked up by their stems.
+    fn fuzzy_expansion(word: &str, distance: u8, vocabulary: &Vocabulary) -> Vec<String> {
+        let mut words = Vec::new();
+        for variant in tokenizer::stem_variants(word) {
+            for word in vocabulary.expand(TermMatcher::Fuzzy(&variant, distance)) {
+                if !words.contains(&word) {
+                    words.push(word);
+                }
+            }
+        }
+        words
+    }
+
+    fn expand(&self, vocabulary: &Vocabulary) -> QueryComp {
+        match self {
+            QueryComp::Word(_) | QueryComp::Filter { .. } => self.clone(),
+            QueryComp::Not(comp) => QueryComp::Not(Box::new(comp.expand(vocabulary))),
+            QueryComp::NAmong { n, among } => QueryComp::NAmong {
+                n: *n,
+                among: among.iter().map(|comp| comp.expand(vocabulary)).collect(),
+            },
+            QueryComp::Phrase(words) => QueryComp::Phrase(words.iter().map(|comp| comp.expand(vocabulary)).collect()),
+            QueryComp::Prefix { prefix, .. } => QueryComp::NAmong {
+                n: 1,
+                among: vocabulary.expand(TermMatcher::Prefix(prefix)).into_iter().map(QueryComp::Word).collect(),
+            },
+            QueryComp::Fuzzy { word, distance, .. } => QueryComp::NAmong {
+                n: 1,
+                among: QueryComp::fuzzy_expansion(word, *distance, vocabulary).into_iter().map(QueryComp::Word).collect(),
+            },
+        }
+    }
+
+    fn fill_known(&mut self, vocabulary: &Vocabulary) {
+        match self {
+            QueryComp::Word(_) | QueryComp::Filter { .. } => (),
+            QueryComp::Not(comp) => comp.fill_known(vocabulary),
+            QueryComp::NAmong { among: comps, .. } | QueryComp::Phrase(comps) => comps.iter_mut().for_each(|comp| comp.fill_known(vocabulary)),
+            QueryComp::Prefix { prefix, known } => *known = vocabulary.expand(TermMatcher::Prefix(prefix)),
+            QueryComp::Fuzzy { word, distance, known } => *known = QueryComp::fuzzy_expansion(word, *distance, vocabulary),
+        }
+    }
+}
+
+impl Query {
+    /// Replaces prefix and fuzzy terms by the indexed words they match.
+    /// The resulting query only contains exact words and can be looked up in the index.
+    pub fn expand(&self, vocabulary: &Vocabulary) -> Query {
+        Query {
+            root: self.root.expand(vocabulary),
+        }
+    }
+
+    /// Lists the words of our vocabulary matching prefix and fuzzy terms.
+    /// Bloom filters can't be queried for prefixes or typos, so these words are used to route the query instead.
+    pub fn fill_known(&mut self, vocabulary: &Vocabulary) {
+        self.root.fill_known(vocabulary)
+    }
+}
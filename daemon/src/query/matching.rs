@@ -37,10 +37,46 @@ impl QueryComp {
                     false => 0,
                 }
             },
+            // Filters don't know about positions, so phrases are only checked word by word
+            QueryComp::Phrase(words) => {
+                let scores = words.iter().map(|comp| comp.match_score(filter)).collect::<Vec<_>>();
+                match scores.iter().all(|score| *score > 0) {
+                    true => scores.into_iter().sum(),
+                    false => 0,
+                }
+            },
+            // Filters can't be queried for prefixes or typos, so we rely on the words known to the searcher.
+            // Without any known word, the remote vocabulary may still match so the term is kept as a weak match.
+            QueryComp::Prefix { known, .. } => match known.is_empty() {
+                true => 1,
+                false => known.iter().map(|word| filter.get_word::<DocumentIndex>(word) as u32).sum(),
+            },
+            QueryComp::Fuzzy { word, known, .. } => {
+                let score = filter.get_word::<DocumentIndex>(word) as u32 + known.iter().map(|word| filter.get_word::<DocumentIndex>(word) as u32).sum::<u32>();
+                match score == 0 && known.is_empty() {
+                    true => 1,
+                    false => score,
+                }
+            },
+        }
+    }
+
+    /// Lists the positions at which a phrase element appears in a document.
+    /// Phrase elements are words or alternative forms of a word.
+    fn positions(&self, lcid: LocalCid, index: &HashMap<String, HashMap<LocalCid, Posting>>) -> Vec<u32> {
+        match self {
+            QueryComp::Word(word) => index.get(word).and_then(|l| l.get(&lcid)).map(|p| p.positions.clone()).unwrap_or_default(),
+            QueryComp::NAmong { among, .. } => {
+                let mut positions = among.iter().flat_map(|comp| comp.positions(lcid, index)).collect::<Vec<_>>();
+                positions.sort();
+                positions.dedup();
+                positions
+            },
+            _ => Vec::new(),
         }
     }
 
-    fn match_score_index(&self, lcid: LocalCid, index: &HashMap<String, HashMap<LocalCid, f32>>, filters: &HashMap<(String, String), Vec<LocalCid>>) -> f32 {
+    fn match_score_index(&self, lcid: LocalCid, index: &HashMap<String, HashMap<LocalCid, Posting>>, filters: &HashMap<(String, String), Vec<LocalCid>>) -> f32 {
         match self {
             QueryComp::Word(word) => index.get(word).map(|l| l.contains_key(&lcid) as usize as f32).unwrap_or(0.0),
             QueryComp::Filter { name, value } => filters.get(&(name.clone(), value.clone())).map(|l| l.contains(&lcid) as usize as f32).unwrap_or(0.0),
@@ -62,12 +98,25 @@ impl QueryComp {
                     false => 0.0,
                 }
             },
+            QueryComp::Phrase(words) => {
+                let Some((first, rest)) = words.split_first() else {return 0.0};
+                let rest_positions = rest.iter().map(|comp| comp.positions(lcid, index)).collect::<Vec<_>>();
+                let found = first.positions(lcid, index).into_iter().any(|start| {
+                    rest_positions.iter().enumerate().all(|(i, positions)| positions.binary_search(&(start + i as u32 + 1)).is_ok())
+                });
+                match found {
+                    true => 1.0,
+                    false => 0.0,
+                }
+            },
+            // Expanded to words before looking up the index
+            QueryComp::Prefix { .. } | QueryComp::Fuzzy { .. } => 0.0,
         }
     }
 }
 
 impl Query {
-    pub fn matching_docs(&self, index: &HashMap<String, HashMap<LocalCid, f32>>, filters: &HashMap<(String, String), Vec<LocalCid>>) -> Vec<LocalCid> {
+    pub fn matching_docs(&self, index: &HashMap<String, HashMap<LocalCid, Posting>>, filters: &HashMap<(String, String), Vec<LocalCid>>) -> Vec<LocalCid> {
         let positive_terms = self.positive_terms();
         let positive_filters = self.positive_filters();
 
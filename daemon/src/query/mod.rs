@@ -4,5 +4,7 @@ pub use parsing::*;
 mod matching;
 pub use matching::*;
 
+mod expansion;
+
 mod query;
 pub use query::*;
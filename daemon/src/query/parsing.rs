@@ -7,12 +7,69 @@ pub struct Parser {
 
 }
 
+/// Words are tokenized the same way documents are.
+/// A single query word can thus result in multiple terms, all of which are required.
+fn build_word_comp(word: &str) -> QueryComp {
+    let mut words = tokenizer::tokenize(word);
+    match words.len() {
+        0 => build_stemmed_comp(tokenizer::normalize(word)),
+        1 => build_stemmed_comp(words.remove(0)),
+        n => QueryComp::NAmong {
+            n,
+            among: words.into_iter().map(build_stemmed_comp).collect(),
+        },
+    }
+}
+
+/// Documents are indexed with their words stemmed according to their language, which the query doesn't know.
+/// The word thus matches if any of its stems does.
+fn build_stemmed_comp(word: String) -> QueryComp {
+    let mut variants = tokenizer::stem_variants(&word);
+    match variants.len() {
+        1 => QueryComp::Word(variants.remove(0)),
+        _ => QueryComp::NAmong {
+            n: 1,
+            among: variants.into_iter().map(QueryComp::Word).collect(),
+        },
+    }
+}
+
+/// Fuzzy terms without an explicit distance tolerate one typo in short words and two in longer ones.
+fn default_fuzzy_distance(word: &str) -> u8 {
+    match word.chars().count() {
+        0..=5 => 1,
+        _ => 2,
+    }
+}
+
 pub(super) fn build_comp(ident: IdentRef<Ident>) -> QueryComp {
     match ident.as_rule() {
         Rule::word_comp => {
             let word = ident.children().next().unwrap();
             let word = word.children().map(|c| c.as_str()).collect::<Vec<_>>().join("");
-            QueryComp::Word(word.to_lowercase()) // TODO: normalization (cf meilisearch)
+            build_word_comp(&word)
+        },
+        Rule::prefix_comp => {
+            let word = ident.children().next().unwrap();
+            let word = word.children().map(|c| c.as_str()).collect::<Vec<_>>().join("");
+            QueryComp::Prefix {
+                prefix: tokenizer::normalize(&word),
+                known: Vec::new(),
+            }
+        },
+        Rule::fuzzy_comp => {
+            let mut children = ident.children();
+            let word = children.next().unwrap();
+            let word = tokenizer::normalize(&word.children().map(|c| c.as_str()).collect::<Vec<_>>().join(""));
+            let distance = match children.next() {
+                Some(number) => number.as_str().parse::<u8>().unwrap_or(MAX_FUZZY_DISTANCE).min(MAX_FUZZY_DISTANCE),
+                None => default_fuzzy_distance(&word),
+            };
+            QueryComp::Fuzzy {
+                word,
+                distance,
+                known: Vec::new(),
+            }
         },
         Rule::and_comp => {
             let mut children = ident.children().collect::<Vec<_>>();
@@ -47,12 +104,17 @@ pub(super) fn build_comp(ident: IdentRef<Ident>) -> QueryComp {
             }
         },
         Rule::quick_or_comp => {
-            let words = ident.children().map(|c| 
-                c.children().map(|c| c.as_str()).collect::<Vec<_>>().join("").to_lowercase()
-            );
             QueryComp::NAmong {
                 n: 1,
-                among: words.map(QueryComp::Word).collect::<Vec<_>>(),
+                among: ident.children().map(build_comp).collect::<Vec<_>>(),
+            }
+        },
+        Rule::phrase_comp => {
+            let phrase = ident.children().map(|c| c.as_str()).collect::<Vec<_>>().join(" ");
+            let mut words = tokenizer::tokenize(&phrase).into_iter().map(build_stemmed_comp).collect::<Vec<_>>();
+            match words.len() {
+                1 => words.remove(0),
+                _ => QueryComp::Phrase(words),
             }
         },
         Rule::not_comp => {
@@ -103,4 +165,24 @@ fn test() {
     let input = "chloe helloco";
     let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
     println!("{:#?}", output);
+
+    let input = "Café 東京都";
+    let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
+    assert_eq!(output.positive_terms(), vec!["cafe", "caf", "東京", "京都"]);
+
+    let input = "rust \"peer to peer\"";
+    let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
+    let QueryComp::NAmong { among, .. } = output.root else {panic!()};
+    assert!(matches!(&among[1], QueryComp::Phrase(words) if words.len() == 2));
+
+    let input = "rust* tokio~ serde~2";
+    let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
+    let QueryComp::NAmong { among, .. } = output.root else {panic!()};
+    assert!(matches!(&among[0], QueryComp::Prefix { prefix, .. } if prefix == "rust"));
+    assert!(matches!(&among[1], QueryComp::Fuzzy { word, distance: 1, .. } if word == "tokio"));
+    assert!(matches!(&among[2], QueryComp::Fuzzy { word, distance: 2, .. } if word == "serde"));
+
+    let input = "running";
+    let output = Query::parse(input).unwrap_or_else(|e| {e.print(input); panic!()});
+    assert!(output.positive_terms().contains(&&String::from("run")));
 }
@@ -24,6 +24,45 @@ impl Query {
     pub fn positive_filters(&self) -> Vec<(&String, &String)> {
         self.root.positive_filters()
     }
+
+    pub fn filters(&self) -> Vec<(&String, &String)> {
+        self.root.filters()
+    }
+
+    /// Same as [Query::positive_terms] but tells how each term matches words.
+    pub fn positive_matchers(&self) -> Vec<TermMatcher<'_>> {
+        self.root.positive_matchers()
+    }
+}
+
+/// Fuzzy terms tolerating more typos would match most of the vocabulary
+pub const MAX_FUZZY_DISTANCE: u8 = 2;
+
+/// How a query term matches words
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TermMatcher<'a> {
+    Exact(&'a String),
+    Prefix(&'a String),
+    Fuzzy(&'a String, u8),
+}
+
+impl<'a> TermMatcher<'a> {
+    pub fn term(&self) -> &'a String {
+        match self {
+            TermMatcher::Exact(term) | TermMatcher::Prefix(term) | TermMatcher::Fuzzy(term, _) => term,
+        }
+    }
+
+    pub fn matches(&self, word: &str) -> bool {
+        match self {
+            TermMatcher::Exact(term) => *term == word,
+            TermMatcher::Prefix(prefix) => word.starts_with(prefix.as_str()),
+            TermMatcher::Fuzzy(term, distance) => {
+                term.chars().count().abs_diff(word.chars().count()) <= *distance as usize
+                    && tokenizer::edit_distance(term, word) <= *distance as usize
+            },
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -42,6 +81,24 @@ pub enum QueryComp {
         n: usize,
         among: Vec<QueryComp>,
     },
+    // "word word word"
+    // Each element is a word, or the alternative forms of a word
+    Phrase(Vec<QueryComp>),
+    // prefix*
+    Prefix {
+        prefix: String,
+        /// Words known to the searcher that start with the prefix, used to route the query
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        known: Vec<String>,
+    },
+    // word~distance
+    Fuzzy {
+        word: String,
+        distance: u8,
+        /// Words known to the searcher that are within the distance, used to route the query
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        known: Vec<String>,
+    },
 }
 
 impl QueryComp {
@@ -67,6 +124,8 @@ impl QueryComp {
                     false => Some(QueryComp::NAmong { n, among: new_among }),
                 }
             },
+            QueryComp::Phrase(words) => Some(QueryComp::Phrase(words.clone())),
+            QueryComp::Prefix { .. } | QueryComp::Fuzzy { .. } => Some(self.clone()),
         }
     }
 
@@ -76,6 +135,9 @@ impl QueryComp {
             QueryComp::Filter { .. } => Vec::new(),
             QueryComp::Not(_) => Vec::new(),
             QueryComp::NAmong { among, .. } => among.iter().flat_map(|c| c.positive_terms()).collect::<Vec<_>>(),
+            QueryComp::Phrase(words) => words.iter().flat_map(|c| c.positive_terms()).collect::<Vec<_>>(),
+            QueryComp::Prefix { prefix, .. } => vec![prefix],
+            QueryComp::Fuzzy { word, .. } => vec![word],
         }
     }
 
@@ -85,6 +147,9 @@ impl QueryComp {
             QueryComp::Filter { .. } => Vec::new(),
             QueryComp::Not(comp) => comp.terms(),
             QueryComp::NAmong { among, .. } => among.iter().flat_map(|c| c.terms()).collect::<Vec<_>>(),
+            QueryComp::Phrase(words) => words.iter().flat_map(|c| c.terms()).collect::<Vec<_>>(),
+            QueryComp::Prefix { prefix, .. } => vec![prefix],
+            QueryComp::Fuzzy { word, .. } => vec![word],
         }
     }
 
@@ -94,6 +159,9 @@ impl QueryComp {
             QueryComp::Filter { .. } => panic!("QueryComp::weighted_terms() called on filter"),
             QueryComp::Not(_) => panic!("QueryComp::weighted_terms() called on not"),
             QueryComp::NAmong { among, .. } => among.iter().flat_map(|c| c.weighted_terms(weight/(among.len() as f64))).collect::<Vec<_>>(), // FIXME: handle 0
+            QueryComp::Phrase(words) => words.iter().flat_map(|c| c.weighted_terms(weight/(words.len() as f64))).collect::<Vec<_>>(),
+            QueryComp::Prefix { prefix, .. } => vec![(prefix.to_string(), weight)],
+            QueryComp::Fuzzy { word, .. } => vec![(word.to_string(), weight)],
         }
     }
 
@@ -103,6 +171,31 @@ impl QueryComp {
             QueryComp::Filter { name, value } => vec![(name, value)],
             QueryComp::Not(_) => Vec::new(),
             QueryComp::NAmong { among, .. } => among.iter().flat_map(|c| c.positive_filters()).collect::<Vec<_>>(),
+            QueryComp::Phrase(_) => Vec::new(),
+            QueryComp::Prefix { .. } | QueryComp::Fuzzy { .. } => Vec::new(),
+        }
+    }
+
+    pub fn filters(&self) -> Vec<(&String, &String)> {
+        match self {
+            QueryComp::Word(_) => Vec::new(),
+            QueryComp::Filter { name, value } => vec![(name, value)],
+            QueryComp::Not(comp) => comp.filters(),
+            QueryComp::NAmong { among, .. } => among.iter().flat_map(|c| c.filters()).collect::<Vec<_>>(),
+            QueryComp::Phrase(_) => Vec::new(),
+            QueryComp::Prefix { .. } | QueryComp::Fuzzy { .. } => Vec::new(),
+        }
+    }
+
+    pub fn positive_matchers(&self) -> Vec<TermMatcher<'_>> {
+        match self {
+            QueryComp::Word(word) => vec![TermMatcher::Exact(word)],
+            QueryComp::Filter { .. } => Vec::new(),
+            QueryComp::Not(_) => Vec::new(),
+            QueryComp::NAmong { among, .. } => among.iter().flat_map(|c| c.positive_matchers()).collect::<Vec<_>>(),
+            QueryComp::Phrase(words) => words.iter().flat_map(|c| c.positive_matchers()).collect::<Vec<_>>(),
+            QueryComp::Prefix { prefix, .. } => vec![TermMatcher::Prefix(prefix)],
+            QueryComp::Fuzzy { word, distance, .. } => vec![TermMatcher::Fuzzy(word, *distance)],
         }
     }
 }
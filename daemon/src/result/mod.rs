@@ -9,3 +9,5 @@ mod word_count;
 pub use word_count::*;
 
 mod validation;
+
+mod verification;
@@ -71,6 +71,13 @@ impl WordCount {
             + self.s() as f64 * 0.1
     }
 
+    /// Adds the counters of another count to this one.
+    pub fn merge(&mut self, other: &WordCount) {
+        for (count, other_count) in self.data.iter_mut().zip(other.data.iter()) {
+            *count += other_count;
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn add(&mut self, h1: bool, h2: bool, h3: bool, h4: bool, h5: bool, h6: bool, strong: bool, em: bool, small: bool, s: bool) {
         if h1 { self.data[0] += 1; return }
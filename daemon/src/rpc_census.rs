@@ -38,6 +38,10 @@ pub struct Record {
     pub peer_id: String,
     pub addrs: Vec<String>,
     pub folders: Vec<(String, u64)>,
+    /// Unix timestamp of when the record was signed
+    pub issued_at: u64,
+    /// Must increase with each submission, so that the census can refuse replayed records
+    pub seq: u64,
 }
 
 pub async fn submit_census_record(census_rpc: &str, record: Record, keys: Keypair) -> Result<(), CensusRpcError> {
@@ -11,13 +11,29 @@ pub enum IpfsRpcError {
 
 impl From<reqwest::Error> for IpfsRpcError {
     fn from(e: reqwest::Error) -> Self {
-        IpfsRpcError::Reqwest(e)
+        let e = IpfsRpcError::Reqwest(e);
+        METRICS.record_ipfs_rpc_error(&e);
+        e
     }
 }
 
 impl From<serde_json::Error> for IpfsRpcError {
     fn from(e: serde_json::Error) -> Self {
-        IpfsRpcError::Json(e)
+        let e = IpfsRpcError::Json(e);
+        METRICS.record_ipfs_rpc_error(&e);
+        e
+    }
+}
+
+/// Errors are recorded in metrics when converted into an [IpfsRpcError], so that only returned errors are counted.
+/// Responses are checked with `ok_or`, which builds its error eagerly, hence this intermediary type.
+struct InvalidResponse(&'static str);
+
+impl From<InvalidResponse> for IpfsRpcError {
+    fn from(e: InvalidResponse) -> Self {
+        let e = IpfsRpcError::InvalidResponse(e.0);
+        METRICS.record_ipfs_rpc_error(&e);
+        e
     }
 }
 
@@ -31,7 +47,6 @@ impl std::fmt::Display for IpfsRpcError {
     }
 }
 
-use IpfsRpcError::InvalidResponse;
 use reqwest::StatusCode;
 
 pub async fn list_pinned(ipfs_rpc: &str) -> Result<Vec<String>, IpfsRpcError> {
@@ -164,7 +179,7 @@ pub async fn add_pin(ipfs_rpc: &str, cid: &str) -> Result<(), IpfsRpcError> {
         _ => {
             let rep = rep.text().await?;
             error!("Failed to replace pin: {rep}");
-            Err(InvalidResponse("Status code not OK"))
+            Err(InvalidResponse("Status code not OK").into())
         },
     }
 }
@@ -177,7 +192,7 @@ pub async fn remove_pin(ipfs_rpc: &str, cid: &str) -> Result<(), IpfsRpcError> {
         _ => {
             let rep = rep.text().await?;
             error!("Failed to replace pin: {rep}");
-            Err(InvalidResponse("Status code not OK"))
+            Err(InvalidResponse("Status code not OK").into())
         },
     }
 }
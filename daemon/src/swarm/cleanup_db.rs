@@ -1,17 +1,12 @@
 use crate::prelude::*;
 
-/// Removes entries older than 1 week in the known peers database.
+/// Removes outdated entries in the known peers database.
 pub async fn cleanup_db_task(controller: NodeController) {
     loop {
         let mut known_peers = controller.sw.known_peers.write().await;
         let previous_len = known_peers.len();
         let now = now();
-        known_peers.retain(|_, info| {
-            match info.last_updated() {
-                Some(last_updated) => now - last_updated < 7*86400,
-                None => false,
-            }
-        });
+        known_peers.retain(|_, info| !info.is_outdated(now));
         let new_len = known_peers.len();
         drop(known_peers);
         if new_len != previous_len {
@@ -77,21 +77,98 @@ async fn get_peers_from_ipfs(node: NodeController, config: Arc<Args>) {
 }
 
 /// Asks our peers for a list of their peers.
+/// Returned peers are merged by [SwarmManager::on_peers_discovered] when the node receives the discovery event.
 async fn get_peers_from_others(node: NodeController, _config: Arc<Args>) {
     let connected_peers = node.sw.connected_peers.read().await.keys().cloned().collect::<Vec<_>>();
 
     let queries = connected_peers.into_iter().map(|p| {
-        PeerListQuery::new(p) // TODO more params
+        PeerListQuery::new(p).with_protocol(String::from(KAMILATA_PROTOCOL))
     });
 
     let mut tasks: Vec<BoxFuture<_>> = Vec::new();
     for q in queries {
+        let peer_id = q.peer_id;
         let fut = node.query_peers(q);
         let fut = timeout(Duration::from_secs(25), fut);
-        tasks.push(Box::pin(fut))
+        tasks.push(Box::pin(async move { (peer_id, fut.await) }))
     }
 
-    join_all(tasks).await;
+    for (peer_id, response) in join_all(tasks).await {
+        match response {
+            Ok(Ok(DiscoveryResponse::Peers(peers))) => trace!("{peer_id} returned {} peers", peers.len()),
+            Ok(Ok(_)) => debug!("Unexpected discovery response from {peer_id}"),
+            Ok(Err(e)) => debug!("Failed to get peers from {peer_id}: {e}"),
+            Err(_) => debug!("Getting peers from {peer_id} timed out"),
+        }
+    }
+}
+
+/// Discovered peers are only useful if we can dial them.
+fn is_valid_listen_addr(peer_id: &PeerId, addr: &Multiaddr) -> bool {
+    let mut components = addr.iter();
+    let host_ok = match components.next() {
+        Some(Protocol::Ip4(ip)) => !ip.is_loopback() && !ip.is_unspecified() && !ip.is_broadcast() && !ip.is_multicast(),
+        Some(Protocol::Ip6(ip)) => !ip.is_loopback() && !ip.is_unspecified() && !ip.is_multicast(),
+        Some(Protocol::Dns(_) | Protocol::Dns4(_) | Protocol::Dns6(_)) => true,
+        _ => false,
+    };
+    let port_ok = matches!(components.next(), Some(Protocol::Tcp(port)) if port != 0);
+    let rest_ok = components.all(|c| match c {
+        Protocol::P2p(id) => id == *peer_id,
+        _ => false,
+    });
+    host_ok && port_ok && rest_ok
+}
+
+impl SwarmManager {
+    /// Merges peers returned by another peer through discovery.
+    /// Addresses are taken from the peer's signed record when there is one.
+    /// Unsigned entries are ignored for peers known to sign their records.
+    pub async fn on_peers_discovered(&self, from: PeerId, peers: HashMap<PeerId, DiscoveredPeer>) {
+        let now = now();
+        let mut known_peers = self.known_peers.write().await;
+        let previous_len = known_peers.len();
+        let mut useful = false;
+        for (peer_id, peer) in peers {
+            if peer_id == from {
+                continue;
+            }
+            let known_seq = known_peers.get(&peer_id).and_then(|p| p.record_seq);
+            let (protocols, listen_addrs, seq) = match peer.record {
+                Some(record) if known_seq.map(|s| record.seq >= s).unwrap_or(true) => (record.protocols, record.listen_addrs, Some(record.seq)),
+                Some(_) => continue, // Outdated record
+                None if known_seq.is_some() => continue,
+                None => (peer.info.protocols, peer.info.listen_addrs, None),
+            };
+            if !protocols.iter().any(|p| p == KAMILATA_PROTOCOL) {
+                continue;
+            }
+            let addrs = listen_addrs.into_iter().filter(|a| is_valid_listen_addr(&peer_id, a)).collect::<Vec<_>>();
+            if addrs.is_empty() {
+                continue;
+            }
+            let known_peer = known_peers.entry(peer_id).or_default();
+            if seq.is_some() {
+                known_peer.record_seq = seq;
+            }
+            for addr in addrs {
+                if !known_peer.addrs.contains(&addr) {
+                    known_peer.addrs.push(addr);
+                }
+            }
+            known_peer.recommended_by.insert(from, now);
+            useful = true;
+        }
+        let new_len = known_peers.len();
+        drop(known_peers);
+
+        if useful {
+            self.record(&from, ReputationEvent::ReturnedPeers).await;
+        }
+        if new_len != previous_len {
+            debug!("Got {} new peers from {from}", new_len - previous_len);
+        }
+    }
 }
 
 pub async fn get_peers(node: NodeController, config: Arc<Args>) {
@@ -8,19 +8,33 @@ pub async fn maintain_swarm_task(controller: NodeController, config: Arc<Args>)
     loop {
         // Unselect all first-class peers that are not seeding
         // Disconnect all transient peers that are staying too long
-        for (peer_id, info) in sw.connected_peers.write().await.iter_mut() {
-            match info.class() {
-                PeerClass::First => if !info.seeding && info.connected_since.elapsed() > Duration::from_secs(60) {
-                    info.selected = false;
-                    // TODO visility in discovery
-                },
-                PeerClass::Transient => if info.connected_since.elapsed() > Duration::from_secs(60) {
-                    debug!("Disconnecting transient peer {peer_id}");
-                    controller.disconnect(peer_id).await;
-                },
-                _ => (),
+        // Disconnect banned peers
+        // Locks are released before disconnecting as the node updates the swarm manager when handling disconnections
+        let mut to_disconnect = Vec::new();
+        {
+            let known_peers = sw.known_peers.read().await;
+            for (peer_id, info) in sw.connected_peers.write().await.iter_mut() {
+                if known_peers.get(peer_id).map(|i| i.banned).unwrap_or(false) {
+                    debug!("Disconnecting banned peer {peer_id}");
+                    to_disconnect.push(*peer_id);
+                    continue;
+                }
+                match info.class() {
+                    PeerClass::First => if !info.seeding && info.connected_since.elapsed() > Duration::from_secs(60) {
+                        info.selected = false;
+                        // TODO visility in discovery
+                    },
+                    PeerClass::Transient => if info.connected_since.elapsed() > Duration::from_secs(60) {
+                        debug!("Disconnecting transient peer {peer_id}");
+                        to_disconnect.push(*peer_id);
+                    },
+                    _ => (),
+                }
             }
         }
+        for peer_id in to_disconnect {
+            controller.disconnect(&peer_id).await;
+        }
 
         // Looking for more peers
         sw.sweep_dial_attempts().await;
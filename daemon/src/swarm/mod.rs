@@ -44,10 +44,13 @@
 //! - Asks for our peers: -2
 //! - Queries us: -5
 //! - Disconnects: -5
+//! - Causes a leecher to be evicted: -20
 //! - Incorrect dns link: -100
 //! - Returned result is automatically downvoted: -500
 //! - Returned result is downvoted by the user: -5000
 //! - Lies on document content: banned
+//! 
+//! Rewards for results and user votes are only recorded once per search and peer.
 
 use crate::prelude::*;
 
@@ -55,13 +58,21 @@ mod maintain_swarm;
 mod cleanup_db;
 mod update_census;
 mod get_peers;
-pub use {maintain_swarm::*, cleanup_db::*, update_census::*, get_peers::*};
+mod status;
+mod peer_store;
+mod reputation;
+pub use {maintain_swarm::*, cleanup_db::*, update_census::*, get_peers::*, status::*, peer_store::*, reputation::*};
+
+/// Known peers that haven't been updated for this long are forgotten (in seconds)
+const KNOWN_PEER_TTL: u64 = 7*86400;
 
 struct ConnectedPeerInfo {
     selected: bool,
     seeding: bool,
     leeching: bool,
     connected_since: Instant,
+    /// Whether we are the ones closing the connection
+    disconnect_requested: bool,
 }
 
 #[derive(Clone, Default)]
@@ -77,6 +88,12 @@ pub struct PeerInfo {
     last_seen: Option<u64>,
     last_returned_by_census: Option<u64>,
     recommended_by: HashMap<PeerId, u64>,
+    /// Sequence number of the latest signed discovery record verified for this peer
+    record_seq: Option<u64>,
+
+    banned: bool,
+    /// Timestamp before which the peer asked us not to come back
+    try_again_at: Option<u64>,
 }
 
 impl PeerInfo {
@@ -94,6 +111,21 @@ impl PeerInfo {
         latest
     }
 
+    /// Banned peers are never outdated, as forgetting them would lift their ban.
+    pub fn is_outdated(&self, now: u64) -> bool {
+        if self.banned {
+            return false;
+        }
+        match self.last_updated() {
+            Some(last_updated) => now.saturating_sub(last_updated) >= KNOWN_PEER_TTL,
+            None => true,
+        }
+    }
+
+    pub fn is_backed_off(&self, now: u64) -> bool {
+        self.try_again_at.map(|t| now < t).unwrap_or(false)
+    }
+
     pub fn availability(&self) -> f64 {
         self.successful_dials as f64 / (self.successful_dials + self.failed_dials) as f64
     }
@@ -109,12 +141,17 @@ impl PeerInfo {
         if self.last_seen.is_some() {
             reliability += 100;
         }
-        reliability += std::cmp::min(self.recommended_by.len() * 5, 90);
+        // Addresses relayed without the peer's signature could have been forged by the recommenders
+        reliability += match self.record_seq.is_some() {
+            true => std::cmp::min(self.recommended_by.len() * 5, 90),
+            false => std::cmp::min(self.recommended_by.len(), 18),
+        };
         reliability
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PeerClass {
     First,
     Second,
@@ -187,11 +224,14 @@ impl SwarmManager {
         let known_peers = self.known_peers.read().await;
         let dial_attempts = self.dial_attemps.read().await;
         let connected_peers = self.connected_peers.read().await;
+        let now = now();
 
         let mut candidates = known_peers
             .iter()
-            .filter(|(peer_id, _)| 
-                !connected_peers.get(peer_id).map(|i| i.selected).unwrap_or(false)
+            .filter(|(peer_id, info)| 
+                !info.banned
+                && !info.is_backed_off(now)
+                && !connected_peers.get(peer_id).map(|i| i.selected).unwrap_or(false)
                 && (!dial_attempts.contains_key(peer_id) || connected_peers.contains_key(peer_id))
             )
             .collect::<Vec<_>>();
@@ -224,6 +264,7 @@ impl SwarmManager {
             seeding: false,
             leeching: false,
             connected_since: Instant::now(),
+            disconnect_requested: false,
         });
         let mut peer_info = known_peers.entry(peer_id).or_default();
         peer_info.last_seen = Some(now());
@@ -233,15 +274,24 @@ impl SwarmManager {
             }
             peer_info.failed_dials = peer_info.failed_dials.saturating_sub(1);
             peer_info.successful_dials += 1;
+            peer_info.apply(ReputationEvent::SuccessfulDial);
         }
     }
 
+    pub async fn on_disconnect_requested(&self, peer_id: &PeerId) {
+        let mut connected_peers = self.connected_peers.write().await;
+        connected_peers.entry(*peer_id).and_modify(|i| i.disconnect_requested = true);
+    }
+
     pub async fn on_peer_disconnected(&self, peer_id: &PeerId) {
         let mut known_peers = self.known_peers.write().await;
         let mut connected_peers = self.connected_peers.write().await;
-        connected_peers.remove(peer_id);
+        let connected_info = connected_peers.remove(peer_id);
         let mut peer_info = known_peers.entry(*peer_id).or_default();
         peer_info.last_seen = Some(now());
+        if connected_info.map(|i| !i.disconnect_requested).unwrap_or(false) {
+            peer_info.apply(ReputationEvent::Disconnected);
+        }
     }
 
     pub async fn on_identify(&self, peer_id: &PeerId, info: libp2p_identify::Info) {
@@ -256,14 +306,40 @@ impl SwarmManager {
         connected_peers.entry(peer_id).and_modify(|i| i.seeding = true);
     }
 
-    pub async fn on_leecher_added(&self, peer_id: PeerId) -> Result<(), TooManyLeechers> {
+    /// When all leecher slots are taken, the leecher with the lowest score is evicted.
+    /// Returns the peer to evict, which might be the new leecher itself.
+    pub async fn on_leecher_added(&self, peer_id: PeerId) -> Option<PeerId> {
+        let mut known_peers = self.known_peers.write().await;
         let mut connected_peers = self.connected_peers.write().await;
         connected_peers.entry(peer_id).and_modify(|i| i.leeching = true);
-        let leecher_count = connected_peers.values().filter(|i| i.class() == PeerClass::Second).count();
-        if leecher_count > self.config.leechers {
-            return Err(TooManyLeechers{})
+        let leechers = connected_peers.iter().filter(|(_, i)| i.class() == PeerClass::Second).map(|(p, _)| *p).collect::<Vec<_>>();
+        if leechers.len() <= self.config.leechers {
+            return None;
+        }
+
+        let score = |peer_id: &PeerId| known_peers.get(peer_id).map(|i| i.score).unwrap_or_default();
+        let new_score = score(&peer_id);
+        let lowest = leechers
+            .into_iter()
+            .filter(|p| *p != peer_id)
+            .min_by(|a, b| score(a).partial_cmp(&score(b)).unwrap_or(Ordering::Equal));
+        match lowest {
+            Some(lowest) if score(&lowest) < new_score => {
+                known_peers.entry(peer_id).or_default().apply(ReputationEvent::CausedEviction);
+                Some(lowest)
+            },
+            _ => Some(peer_id),
         }
-        Ok(())
+    }
+
+    /// Called when a peer refuses or stops seeding to us.
+    /// It won't be selected again before the delay it asked for has elapsed.
+    pub async fn on_kamilata_disconnect(&self, peer_id: &PeerId, try_again_in: Option<Duration>) {
+        let mut known_peers = self.known_peers.write().await;
+        let mut connected_peers = self.connected_peers.write().await;
+        let peer_info = known_peers.entry(*peer_id).or_default();
+        peer_info.try_again_at = Some(try_again_in.map(|d| now() + d.as_secs()).unwrap_or(u64::MAX));
+        connected_peers.entry(*peer_id).and_modify(|i| i.selected = false);
     }
 
     pub async fn on_seeder_removed(&self, peer_id: &PeerId) {
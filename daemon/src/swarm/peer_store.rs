@@ -0,0 +1,193 @@
+use super::*;
+
+/// Known peers are saved to disk this often
+const SAVE_INTERVAL: Duration = Duration::from_secs(5*60);
+
+/// Serializable form of a [PeerInfo]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredPeerInfo {
+    pub peer_id: String,
+    addrs: Vec<String>,
+    score: f32,
+    recommander_score: f32,
+    successful_dials: u64,
+    failed_dials: u64,
+    last_seen_ipfs: Option<u64>,
+    last_seen: Option<u64>,
+    last_returned_by_census: Option<u64>,
+    recommended_by: Vec<(String, u64)>,
+    #[serde(default)]
+    record_seq: Option<u64>,
+    #[serde(default)]
+    banned: bool,
+}
+
+impl StoredPeerInfo {
+    fn new(peer_id: &PeerId, info: &PeerInfo) -> StoredPeerInfo {
+        StoredPeerInfo {
+            peer_id: peer_id.to_string(),
+            addrs: info.addrs.iter().map(|a| a.to_string()).collect(),
+            score: info.score,
+            recommander_score: info.recommander_score,
+            successful_dials: info.successful_dials,
+            failed_dials: info.failed_dials,
+            last_seen_ipfs: info.last_seen_ipfs,
+            last_seen: info.last_seen,
+            last_returned_by_census: info.last_returned_by_census,
+            recommended_by: info.recommended_by.iter().map(|(p, t)| (p.to_string(), *t)).collect(),
+            record_seq: info.record_seq,
+            banned: info.banned,
+        }
+    }
+
+    /// Returns None if the peer id is invalid.
+    /// Invalid addresses and recommenders are skipped.
+    fn into_peer_info(self) -> Option<(PeerId, PeerInfo)> {
+        let peer_id = self.peer_id.parse().ok()?;
+        let info = PeerInfo {
+            addrs: self.addrs.iter().filter_map(|a| a.parse().ok()).collect(),
+            score: self.score,
+            recommander_score: self.recommander_score,
+            successful_dials: self.successful_dials,
+            failed_dials: self.failed_dials,
+            last_seen_ipfs: self.last_seen_ipfs,
+            last_seen: self.last_seen,
+            last_returned_by_census: self.last_returned_by_census,
+            recommended_by: self.recommended_by.into_iter().filter_map(|(p, t)| Some((p.parse().ok()?, t))).collect(),
+            record_seq: self.record_seq,
+            banned: self.banned,
+            try_again_at: None,
+        };
+        Some((peer_id, info))
+    }
+}
+
+#[derive(Debug)]
+pub enum PeerStoreError {
+    Io(IoError),
+    Json(serde_json::Error),
+    #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
+    Db(DbError),
+}
+
+impl From<IoError> for PeerStoreError {
+    fn from(e: IoError) -> Self {
+        PeerStoreError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PeerStoreError {
+    fn from(e: serde_json::Error) -> Self {
+        PeerStoreError::Json(e)
+    }
+}
+
+#[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
+impl From<DbError> for PeerStoreError {
+    fn from(e: DbError) -> Self {
+        PeerStoreError::Db(e)
+    }
+}
+
+/// Where known peers are saved, so that they survive restarts.
+/// That's the database if there is one, or a standalone file.
+#[derive(Clone)]
+pub struct PeerStore {
+    #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
+    db: DbPeersController,
+    #[cfg(not(any(feature = "database-lmdb", feature = "database-mdbx")))]
+    path: String,
+}
+
+impl PeerStore {
+    #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
+    pub async fn new(_config: &Args, index: &DocumentIndex) -> PeerStore {
+        PeerStore {
+            db: index.peers_db().await,
+        }
+    }
+
+    #[cfg(not(any(feature = "database-lmdb", feature = "database-mdbx")))]
+    pub async fn new(config: &Args, _index: &DocumentIndex) -> PeerStore {
+        PeerStore {
+            path: config.known_peers_path.clone(),
+        }
+    }
+
+    #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
+    async fn load(&self) -> Result<Vec<StoredPeerInfo>, PeerStoreError> {
+        Ok(self.db.get().await?)
+    }
+
+    #[cfg(not(any(feature = "database-lmdb", feature = "database-mdbx")))]
+    async fn load(&self) -> Result<Vec<StoredPeerInfo>, PeerStoreError> {
+        let data = match tokio::fs::read(&self.path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    #[cfg(any(feature = "database-lmdb", feature = "database-mdbx"))]
+    async fn save(&self, peers: Vec<StoredPeerInfo>) -> Result<(), PeerStoreError> {
+        Ok(self.db.replace(peers).await?)
+    }
+
+    /// Writes to a temporary file first so that a crash can't leave a truncated file behind
+    #[cfg(not(any(feature = "database-lmdb", feature = "database-mdbx")))]
+    async fn save(&self, peers: Vec<StoredPeerInfo>) -> Result<(), PeerStoreError> {
+        let data = serde_json::to_vec(&peers)?;
+        let tmp_path = format!("{}.tmp", self.path);
+        tokio::fs::write(&tmp_path, data).await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+}
+
+impl SwarmManager {
+    /// Restores known peers saved by a previous run, skipping outdated ones.
+    pub async fn load_known_peers(&self, store: &PeerStore) {
+        let stored = match store.load().await {
+            Ok(stored) => stored,
+            Err(e) => {
+                error!("Failed to load known peers: {e:?}");
+                return;
+            }
+        };
+
+        let now = now();
+        let mut known_peers = self.known_peers.write().await;
+        for (peer_id, info) in stored.into_iter().filter_map(|p| p.into_peer_info()) {
+            if !info.is_outdated(now) {
+                known_peers.entry(peer_id).or_insert(info);
+            }
+        }
+        if !known_peers.is_empty() {
+            debug!("Restored {} known peers from disk", known_peers.len());
+        }
+    }
+
+    pub async fn save_known_peers(&self, store: &PeerStore) {
+        let now = now();
+        let peers = self.known_peers
+            .read().await
+            .iter()
+            .filter(|(_, info)| !info.is_outdated(now))
+            .map(|(peer_id, info)| StoredPeerInfo::new(peer_id, info))
+            .collect::<Vec<_>>();
+        let count = peers.len();
+        match store.save(peers).await {
+            Ok(()) => trace!("Saved {count} known peers"),
+            Err(e) => error!("Failed to save known peers: {e:?}"),
+        }
+    }
+}
+
+/// Periodically saves known peers to disk.
+pub async fn save_known_peers_task(controller: NodeController, store: PeerStore) {
+    loop {
+        sleep(SAVE_INTERVAL).await;
+        controller.sw.save_known_peers(&store).await;
+    }
+}
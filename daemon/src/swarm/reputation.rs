@@ -0,0 +1,95 @@
+use super::*;
+
+/// Events affecting the reputation of a peer, as listed in the [module documentation](super)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReputationEvent {
+    UserUpvote,
+    AutoUpvote,
+    ReturnedResults,
+    CorrectDnsLink,
+    SuccessfulDial,
+    ReturnedPeers,
+    FirstClassSentFilters,
+    OtherSentFilters,
+    AskedPeers,
+    Queried,
+    Disconnected,
+    CausedEviction,
+    IncorrectDnsLink,
+    AutoDownvote,
+    UserDownvote,
+    LiedOnContent,
+}
+
+impl ReputationEvent {
+    pub fn impact(&self) -> f32 {
+        match self {
+            ReputationEvent::UserUpvote => 5000.0,
+            ReputationEvent::AutoUpvote => 500.0,
+            ReputationEvent::ReturnedResults => 100.0,
+            ReputationEvent::CorrectDnsLink => 20.0,
+            ReputationEvent::SuccessfulDial => 10.0,
+            ReputationEvent::ReturnedPeers => 5.0,
+            ReputationEvent::FirstClassSentFilters => 0.2,
+            ReputationEvent::OtherSentFilters => 0.1,
+            ReputationEvent::AskedPeers => -2.0,
+            ReputationEvent::Queried => -5.0,
+            ReputationEvent::Disconnected => -5.0,
+            ReputationEvent::CausedEviction => -20.0,
+            ReputationEvent::IncorrectDnsLink => -100.0,
+            ReputationEvent::AutoDownvote => -500.0,
+            ReputationEvent::UserDownvote => -5000.0,
+            ReputationEvent::LiedOnContent => 0.0,
+        }
+    }
+}
+
+/// Score given to peers that recommended a peer that returns search results
+const RECOMMANDER_IMPACT: f32 = 10.0;
+
+impl PeerInfo {
+    pub(super) fn apply(&mut self, event: ReputationEvent) {
+        self.score += event.impact();
+        if event == ReputationEvent::LiedOnContent {
+            self.banned = true;
+        }
+    }
+}
+
+impl SwarmManager {
+    pub async fn record(&self, peer_id: &PeerId, event: ReputationEvent) {
+        let mut known_peers = self.known_peers.write().await;
+        let peer_info = known_peers.entry(*peer_id).or_default();
+        peer_info.apply(event);
+        match event {
+            ReputationEvent::LiedOnContent => warn!("Banned {peer_id} for lying on document content"),
+            _ => trace!("Reputation of {peer_id} is now {} ({event:?})", peer_info.score),
+        }
+
+        if event == ReputationEvent::ReturnedResults {
+            let recommanders = peer_info.recommended_by.keys().copied().collect::<Vec<_>>();
+            for recommander in recommanders {
+                if let Some(recommander_info) = known_peers.get_mut(&recommander) {
+                    recommander_info.recommander_score += RECOMMANDER_IMPACT;
+                }
+            }
+        }
+    }
+
+    /// Banned peers are refused at connection time and never dialed.
+    pub async fn ban(&self, peer_id: &PeerId) {
+        self.record(peer_id, ReputationEvent::LiedOnContent).await;
+    }
+
+    pub async fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.known_peers.read().await.get(peer_id).map(|i| i.banned).unwrap_or(false)
+    }
+
+    pub async fn on_filters_received(&self, peer_id: &PeerId) {
+        let event = match self.class(peer_id).await {
+            Some(PeerClass::First) => ReputationEvent::FirstClassSentFilters,
+            _ => ReputationEvent::OtherSentFilters,
+        };
+        self.record(peer_id, event).await;
+    }
+}
@@ -0,0 +1,126 @@
+use super::*;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectedPeerStatus {
+    pub peer_id: String,
+    pub class: PeerClass,
+    pub seeding: bool,
+    pub leeching: bool,
+    /// Number of seconds since the connection was established
+    pub connected_for: u64,
+    pub addrs: Vec<String>,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KnownPeerStatus {
+    pub peer_id: String,
+    pub addrs: Vec<String>,
+    pub score: f32,
+    pub successful_dials: u64,
+    pub failed_dials: u64,
+    /// None if the peer was never dialed
+    pub dial_success_ratio: Option<f64>,
+    pub from_census: bool,
+    pub from_ipfs: bool,
+    pub from_discovery: bool,
+    pub connected: bool,
+    pub banned: bool,
+}
+
+/// Peers can be known from several sources, so the sources don't add up to the total.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KnownPeerCounts {
+    pub total: usize,
+    pub census: usize,
+    pub ipfs: usize,
+    pub discovery: usize,
+    /// Peers we have been connected to
+    pub seen: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SwarmStatus {
+    pub peer_id: String,
+    pub external_addrs: Vec<String>,
+    pub first_class: usize,
+    pub first_class_target: usize,
+    pub second_class: usize,
+    pub second_class_limit: usize,
+    pub transient: usize,
+    pub currently_dialing: usize,
+    pub known_peers: KnownPeerCounts,
+}
+
+impl SwarmManager {
+    pub async fn connected_peers_status(&self) -> Vec<ConnectedPeerStatus> {
+        let known_peers = self.known_peers.read().await;
+        let connected_peers = self.connected_peers.read().await;
+
+        let mut peers = connected_peers.iter().map(|(peer_id, info)| {
+            let known = known_peers.get(peer_id);
+            ConnectedPeerStatus {
+                peer_id: peer_id.to_string(),
+                class: info.class(),
+                seeding: info.seeding,
+                leeching: info.leeching,
+                connected_for: info.connected_since.elapsed().as_secs(),
+                addrs: known.map(|k| k.addrs.iter().map(|a| a.to_string()).collect()).unwrap_or_default(),
+                score: known.map(|k| k.score).unwrap_or_default(),
+            }
+        }).collect::<Vec<_>>();
+        peers.sort_by_key(|p| (p.class as u8, std::cmp::Reverse(p.connected_for)));
+        peers
+    }
+
+    pub async fn known_peers_status(&self) -> Vec<KnownPeerStatus> {
+        let known_peers = self.known_peers.read().await;
+        let connected_peers = self.connected_peers.read().await;
+
+        let mut peers = known_peers.iter().map(|(peer_id, info)| KnownPeerStatus {
+            peer_id: peer_id.to_string(),
+            addrs: info.addrs.iter().map(|a| a.to_string()).collect(),
+            score: info.score,
+            successful_dials: info.successful_dials,
+            failed_dials: info.failed_dials,
+            dial_success_ratio: match info.successful_dials + info.failed_dials {
+                0 => None,
+                _ => Some(info.availability()),
+            },
+            from_census: info.last_returned_by_census.is_some(),
+            from_ipfs: info.last_seen_ipfs.is_some(),
+            from_discovery: !info.recommended_by.is_empty(),
+            connected: connected_peers.contains_key(peer_id),
+            banned: info.banned,
+        }).collect::<Vec<_>>();
+        peers.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        peers
+    }
+
+    pub async fn known_peer_counts(&self) -> KnownPeerCounts {
+        let known_peers = self.known_peers.read().await;
+        let mut counts = KnownPeerCounts { total: known_peers.len(), ..KnownPeerCounts::default() };
+        for info in known_peers.values() {
+            counts.census += info.last_returned_by_census.is_some() as usize;
+            counts.ipfs += info.last_seen_ipfs.is_some() as usize;
+            counts.discovery += !info.recommended_by.is_empty() as usize;
+            counts.seen += info.last_seen.is_some() as usize;
+        }
+        counts
+    }
+
+    pub async fn status(&self, peer_id: PeerId, external_addrs: Vec<String>) -> SwarmStatus {
+        let (first_class, second_class, transient) = self.class_counts().await;
+        SwarmStatus {
+            peer_id: peer_id.to_string(),
+            external_addrs,
+            first_class,
+            first_class_target: self.config.first_class,
+            second_class,
+            second_class_limit: self.config.leechers,
+            transient,
+            currently_dialing: self.currently_dialing().await,
+            known_peers: self.known_peer_counts().await,
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use crate::prelude::*;
 
+/// The census refuses records with more folders
+const MAX_CENSUS_FOLDERS: usize = 500;
+
 pub async fn update_census_task(node: NodeController, index: DocumentIndex, keypair: Keypair, config: Arc<Args>) {
     if !config.census_enabled {
         return;
@@ -12,6 +15,7 @@ pub async fn update_census_task(node: NodeController, index: DocumentIndex, keyp
         },
     };
 
+    let mut seq = 0;
     loop {
         let mut external_addrs = external_addrs.clone();
         let new_addrs = node
@@ -28,11 +32,16 @@ pub async fn update_census_task(node: NodeController, index: DocumentIndex, keyp
             continue;
         }
 
+        let now = now();
         let record = Record {
             peer_id: keypair.public().to_peer_id().to_string(),
             addrs: external_addrs.clone(),
-            folders: Vec::new(), // TODO: Remove folders from census record
+            folders: index.folder_document_counts(MAX_CENSUS_FOLDERS).await,
+            issued_at: now,
+            // Seeded from the clock so that a restarted node's seq still exceeds the census's last one
+            seq: std::cmp::max(seq + 1, now),
         };
+        seq = record.seq;
 
         match submit_census_record(&config.census_rpc, record, keypair.clone()).await {
             Ok(()) => trace!("Submitted census record"),
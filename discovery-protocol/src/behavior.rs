@@ -2,13 +2,23 @@ use super::*;
 
 #[derive(Debug, Clone)]
 pub enum Event {
-
+    /// Sent when a peer answered one of our queries with a list of peers.
+    /// Peer ids that couldn't be parsed and peers with invalid signed records are left out.
+    PeersDiscovered { from: PeerId, peers: HashMap<PeerId, DiscoveredPeer> },
+    /// Sent when a peer asked for our peers, and we answered.
+    PeersRequested { peer_id: PeerId },
 }
 
 pub struct Behaviour {
     config: Arc<Config>,
     db: Arc<Db>,
     events_to_dispatch: Vec<(PeerId, BehaviorToHandlerEvent)>,
+    /// Events produced by query tasks, waiting to be returned to the swarm
+    event_sender: UnboundedSender<Event>,
+    event_receiver: UnboundedReceiver<Event>,
+    /// The last record we signed about ourselves, sent to each new peer
+    local_record: Option<SignedRecord>,
+    local_seq: u64,
 }
 
 impl Behaviour {
@@ -20,16 +30,34 @@ impl Behaviour {
     /// Creates a new behaviour with a custom configuration.
     pub fn new_with_config(config: Config) -> Behaviour {
         let config = Arc::new(config);
+        let (event_sender, event_receiver) = unbounded_channel();
         Behaviour {
             config: Arc::clone(&config),
             db: Arc::new(Db::new(config)),
             events_to_dispatch: Vec::new(),
+            event_sender,
+            event_receiver,
+            local_record: None,
+            // Sequence numbers must keep increasing across restarts
+            local_seq: now(),
         }
     }
 
     /// Sends a query to a peer.
-    /// Results will be returned through the provided channel.
+    /// Results will be returned through the provided channel, and peers will be announced with [Event::PeersDiscovered].
     pub fn start_query(&mut self, query: PeerListQuery, sender: OneshotSender<Result<Response, IoError>>) {
+        let from = query.peer_id;
+        let (inner_sender, inner_receiver) = oneshot_channel();
+        let event_sender = self.event_sender.clone();
+        tokio::spawn(async move {
+            let Ok(result) = inner_receiver.await else { return };
+            if let Ok(Response::Peers(peers)) = &result {
+                let peers = parse_peers(peers.clone());
+                let _ = event_sender.send(Event::PeersDiscovered { from, peers });
+            }
+            let _ = sender.send(result);
+        });
+
         self.events_to_dispatch.push((
             query.peer_id,
             BehaviorToHandlerEvent::Request {
@@ -40,26 +68,18 @@ impl Behaviour {
                     metadata: query.metadata,
                     max_results: query.max_results.unwrap_or(self.config.max_results),
                 },
-                replier: sender
+                replier: inner_sender
             }
         ));
     }
 
     /// Sends a query to a peer.
-    pub async fn query(&mut self, query: PeerListQuery) -> Result<HashMap<PeerId, Info>, IoError> {
+    pub async fn query(&mut self, query: PeerListQuery) -> Result<HashMap<PeerId, DiscoveredPeer>, IoError> {
         let (sender, receiver) = oneshot_channel();
         self.start_query(query, sender);
         let result = receiver.await.map_err(|_| IoError::new(std::io::ErrorKind::BrokenPipe, "Couldn't receive response"))?;
         match result {
-            Ok(Response::Peers(peers)) => {
-                let mut final_peers = HashMap::new();
-                for (peer_id, info) in peers {
-                    if let Ok(peer_id) = peer_id.parse() {
-                        final_peers.insert(peer_id, info);
-                    }
-                }
-                Ok(final_peers)
-            },
+            Ok(Response::Peers(peers)) => Ok(parse_peers(peers)),
             Ok(_) => Err(IoError::new(std::io::ErrorKind::InvalidData, "Unexpected response")),
             Err(e) => Err(e)
         }
@@ -75,9 +95,40 @@ impl Behaviour {
             protocols: info.protocols.into_iter().map(|p| p.as_ref().to_string()).collect(),
             observed_addr: Some(info.observed_addr),
             metadata: Vec::new(),
+            signed_record: None,
         }).await;
     }
 
+    /// Signs a new record about ourselves and sends it to our peers.
+    /// They will relay it to others along with our info, so that our addresses can't be forged.
+    pub async fn set_local_record(&mut self, keypair: &Keypair, listen_addrs: Vec<Multiaddr>, protocols: Vec<String>) -> Result<(), SigningError> {
+        self.local_seq += 1;
+        let record = Record {
+            listen_addrs,
+            protocols,
+            timestamp: now(),
+            seq: self.local_seq,
+        };
+        let signed_record = SignedRecord::sign(keypair, &record)?;
+        for peer_id in self.db.peers().await {
+            self.send_local_record(peer_id, signed_record.clone());
+        }
+        self.local_record = Some(signed_record);
+        Ok(())
+    }
+
+    fn send_local_record(&mut self, peer_id: PeerId, signed_record: SignedRecord) {
+        // Nobody waits for the response
+        let (replier, _) = oneshot_channel();
+        self.events_to_dispatch.push((
+            peer_id,
+            BehaviorToHandlerEvent::Request {
+                request: Request::SetRecord(signed_record),
+                replier
+            }
+        ));
+    }
+
     /// Returns information about a peer.
     pub async fn get_info(&self, peer_id: PeerId) -> Option<Info> {
         self.db.get_info(&peer_id).await
@@ -115,6 +166,9 @@ impl NetworkBehaviour for Behaviour {
     fn on_swarm_event(&mut self, event: FromSwarm) {
         match event {
             FromSwarm::ConnectionEstablished(info) => {
+                if let (0, Some(signed_record)) = (info.other_established, self.local_record.clone()) {
+                    self.send_local_record(info.peer_id, signed_record);
+                }
                 let db = Arc::clone(&self.db);
                 tokio::spawn(async move {
                     db.insert_peer(info.peer_id).await;
@@ -132,8 +186,12 @@ impl NetworkBehaviour for Behaviour {
         }
     }
 
-    fn on_connection_handler_event(&mut self, _peer_id: PeerId, _connection_id: ConnectionId, event: THandlerOutEvent<Self>) {
-        match event {}
+    fn on_connection_handler_event(&mut self, peer_id: PeerId, _connection_id: ConnectionId, event: THandlerOutEvent<Self>) {
+        match event {
+            HandlerToBehaviorEvent::PeersRequested => {
+                let _ = self.event_sender.send(Event::PeersRequested { peer_id });
+            },
+        }
     }
 
     fn handle_established_inbound_connection(
@@ -156,15 +214,37 @@ impl NetworkBehaviour for Behaviour {
         Ok(Handler::new(remote_peer_id, Arc::clone(&self.config), Arc::clone(&self.db)))
     }
 
-    fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
         if let Some((peer_id, event)) = self.events_to_dispatch.pop() {
             return Poll::Ready(ToSwarm::NotifyHandler { peer_id, handler: NotifyHandler::Any, event });
         }
 
+        if let Poll::Ready(Some(event)) = self.event_receiver.poll_recv(cx) {
+            return Poll::Ready(ToSwarm::GenerateEvent(event));
+        }
+
         Poll::Pending
     }
 }
 
+/// Parses peer ids of a [Response::Peers] and verifies signed records, dropping invalid peers.
+fn parse_peers(peers: HashMap<String, Info>) -> HashMap<PeerId, DiscoveredPeer> {
+    peers.into_iter().filter_map(|(peer_id, info)| {
+        let peer_id: PeerId = peer_id.parse().ok()?;
+        let record = match &info.signed_record {
+            Some(signed_record) => match signed_record.verify(&peer_id) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    debug!("Dropping {peer_id} with invalid record: {e}");
+                    return None;
+                }
+            },
+            None => None,
+        };
+        Some((peer_id, DiscoveredPeer { info, record }))
+    }).collect()
+}
+
 impl Default for Behaviour {
     fn default() -> Self {
         Behaviour::new()
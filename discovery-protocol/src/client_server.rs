@@ -12,6 +12,10 @@ pub enum Request {
     /// It can be used to specify which peers are welcome to connect.
     SetMetadata(Vec<u8>),
 
+    /// Provides a record the node signed about itself.
+    /// It will be provided to others along with contact information, so that they can verify it.
+    SetRecord(SignedRecord),
+
     /// Requests the list of peers.
     GetPeers {
         /// If set, the list of peers will be filtered to only include those running the given protocol.
@@ -60,10 +64,12 @@ async fn send_prefixed(stream: &mut Stream, data: &[u8]) -> Result<(), IoError>
     Ok(())
 }
 
-pub async fn server_task(remote_peer_id: PeerId, mut stream: Stream, db: Arc<Db>) -> Result<(), IoError> {
+/// Answers a request, returning an event for the behaviour if it should know about it.
+pub async fn server_task(remote_peer_id: PeerId, mut stream: Stream, db: Arc<Db>) -> Result<Option<HandlerToBehaviorEvent>, IoError> {
     let request = read_prefixed(&mut stream, db.config().request_max_payload_size).await?;
     let request: Request = serde_json::from_slice(&request)?;
 
+    let mut event = None;
     let response = match request {
         Request::SetVisibility(visibility) => {
             db.set_visibility(&remote_peer_id, visibility).await;
@@ -73,6 +79,13 @@ pub async fn server_task(remote_peer_id: PeerId, mut stream: Stream, db: Arc<Db>
             db.set_metadata(&remote_peer_id, metadata).await;
             Response::Ok
         },
+        Request::SetRecord(signed_record) => match signed_record.verify(&remote_peer_id) {
+            Ok(record) => match db.set_record(&remote_peer_id, record.seq, signed_record).await {
+                true => Response::Ok,
+                false => Response::Error(String::from("outdated record")),
+            },
+            Err(e) => Response::Error(e.to_string()),
+        },
         Request::GetPeers { protocol_version, agent_version, protocols, metadata, max_results } => {
             let mut peers = db.gen_list(protocol_version, agent_version, protocols, metadata).await;
             peers.truncate(max_results);
@@ -83,6 +96,7 @@ pub async fn server_task(remote_peer_id: PeerId, mut stream: Stream, db: Arc<Db>
                 final_list.insert(peer_id.to_string(), info);
             }
 
+            event = Some(HandlerToBehaviorEvent::PeersRequested);
             Response::Peers(final_list)
         },
     };
@@ -90,7 +104,7 @@ pub async fn server_task(remote_peer_id: PeerId, mut stream: Stream, db: Arc<Db>
     let response = serde_json::to_vec(&response)?;
     send_prefixed(&mut stream, &response).await?;
 
-    Ok(())
+    Ok(event)
 }
 
 async fn client_task_inner(request: Request, mut stream: Stream, db: Arc<Db>) -> Result<Response, IoError> {
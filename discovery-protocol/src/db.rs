@@ -23,11 +23,18 @@ pub struct Info {
 
     /// Arbitrary metadata provided by the peer.
     pub metadata: Vec<u8>,
+
+    /// The record the peer signed about itself, if any.
+    /// Unlike the other fields, it cannot be forged by the peers relaying it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_record: Option<SignedRecord>,
 }
 
 pub struct Db {
     config: Arc<Config>,
     data: RwLock<HashMap<PeerId, (bool, Info)>>,
+    /// Records signed by peers, with their sequence number
+    records: RwLock<HashMap<PeerId, (u64, SignedRecord)>>,
 }
 
 impl Db {
@@ -35,6 +42,7 @@ impl Db {
         Db {
             config,
             data: RwLock::new(HashMap::new()),
+            records: RwLock::new(HashMap::new()),
         }
     }
 
@@ -44,6 +52,12 @@ impl Db {
 
     pub async fn remove_peer(&self, peer_id: &PeerId) {
         self.data.write().await.remove(peer_id);
+        self.records.write().await.remove(peer_id);
+    }
+
+    /// Returns the ids of all connected peers.
+    pub async fn peers(&self) -> Vec<PeerId> {
+        self.data.read().await.keys().copied().collect()
     }
 
     pub async fn insert_peer(&self, peer_id: PeerId) {
@@ -67,6 +81,18 @@ impl Db {
         self.data.write().await.entry(*peer_id).or_insert((self.config.default_visibility, Info::default())).1.metadata = metadata;
     }
 
+    /// Stores a record a peer signed about itself.
+    /// The record should have been verified beforehand.
+    /// Returns false if it is older than the one we already have.
+    pub async fn set_record(&self, peer_id: &PeerId, seq: u64, record: SignedRecord) -> bool {
+        let mut records = self.records.write().await;
+        if records.get(peer_id).map(|(known_seq, _)| *known_seq >= seq).unwrap_or(false) {
+            return false;
+        }
+        records.insert(*peer_id, (seq, record));
+        true
+    }
+
     pub async fn gen_list(
         &self,
         protocol_version: Option<String>,
@@ -74,7 +100,9 @@ impl Db {
         protocols: Option<Vec<String>>,
         metadata: Option<Vec<u8>>
     ) -> Vec<(PeerId, Info)> {
-        self.data.read().await
+        let data = self.data.read().await;
+        let records = self.records.read().await;
+        data
             .iter()
             .filter(|(_, (visible, _))| *visible)
             .filter(|(_, (_, info))| match protocol_version {
@@ -93,7 +121,37 @@ impl Db {
                 Some(ref metadata) => info.metadata == *metadata,
                 None => true,
             })
-            .map(|(peer_id, (_, info))| (*peer_id, info.clone()))
+            .map(|(peer_id, (_, info))| {
+                let mut info = info.clone();
+                info.signed_record = records.get(peer_id).map(|(_, record)| record.clone());
+                (*peer_id, info)
+            })
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::tests::record;
+
+    #[tokio::test]
+    async fn seq() {
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+        let db = Db::new(Arc::new(Config::default()));
+
+        let signed_record = SignedRecord::sign(&keypair, &record(now(), 5)).unwrap();
+        assert!(db.set_record(&peer_id, 5, signed_record.clone()).await);
+        assert!(!db.set_record(&peer_id, 5, signed_record.clone()).await);
+        assert!(!db.set_record(&peer_id, 4, signed_record).await);
+
+        let newer_record = SignedRecord::sign(&keypair, &record(now(), 6)).unwrap();
+        assert!(db.set_record(&peer_id, 6, newer_record.clone()).await);
+
+        // The latest record is relayed
+        db.set_visibility(&peer_id, true).await;
+        let list = db.gen_list(None, None, None, None).await;
+        assert_eq!(list[0].1.signed_record, Some(newer_record));
+    }
+}
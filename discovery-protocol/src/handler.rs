@@ -9,14 +9,17 @@ pub enum BehaviorToHandlerEvent {
 }
 
 #[derive(Debug)]
-pub enum HandlerToBehaviorEvent {}
+pub enum HandlerToBehaviorEvent {
+    /// The remote peer asked for our peers, and we answered.
+    PeersRequested,
+}
 
 pub struct Handler {
     remote_peer_id: PeerId,
     config: Arc<Config>,
     db: Arc<Db>,
 
-    server_tasks: Vec<BoxFuture<'static, Result<(), IoError>>>,
+    server_tasks: Vec<BoxFuture<'static, Result<Option<HandlerToBehaviorEvent>, IoError>>>,
     client_tasks: Vec<BoxFuture<'static, ()>>,
     pending_requests: Vec<(Request, RequestReplier)>,
 }
@@ -98,6 +101,9 @@ impl ConnectionHandler for Handler {
                 Poll::Ready(result) => {
                     drop(self.server_tasks.remove(0));
                     debug!("Server task finished: {result:?}");
+                    if let Ok(Some(event)) = result {
+                        return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(event));
+                    }
                 },
                 Poll::Pending => (),
             }
@@ -11,7 +11,8 @@ pub(crate) use libp2p::{
         FromSwarm, NetworkBehaviour, SubstreamProtocol, THandlerInEvent,
         THandlerOutEvent, ToSwarm, NotifyHandler, Stream,
     },
-    core::UpgradeInfo, InboundUpgrade, Multiaddr, OutboundUpgrade, PeerId,
+    core::{UpgradeInfo, SignedEnvelope}, identity::{Keypair, SigningError},
+    InboundUpgrade, Multiaddr, OutboundUpgrade, PeerId,
 };
 pub(crate) use log::{debug, error};
 pub(crate) use serde::{Deserialize, Serialize};
@@ -20,9 +21,10 @@ pub(crate) use std::{
     sync::Arc,
     task::{Context, Poll},
     io::Error as IoError,
-    mem::drop
+    mem::drop,
+    time::{SystemTime, UNIX_EPOCH},
 };
-pub(crate) use tokio::sync::{RwLock, oneshot::{Sender as OneshotSender, channel as oneshot_channel}};
+pub(crate) use tokio::sync::{RwLock, oneshot::{Sender as OneshotSender, channel as oneshot_channel}, mpsc::{UnboundedSender, UnboundedReceiver, unbounded_channel}};
 
 mod behavior;
 mod client_server;
@@ -31,7 +33,8 @@ mod protocol;
 mod db;
 mod config;
 mod query;
+mod record;
 
-pub use {behavior::*, client_server::*, handler::*, protocol::*, db::*, config::*, query::*};
+pub use {behavior::*, client_server::*, handler::*, protocol::*, db::*, config::*, query::*, record::*};
 
 pub(crate) type RequestReplier = OneshotSender<Result<Response, IoError>>;
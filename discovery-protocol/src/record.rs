@@ -0,0 +1,138 @@
+use super::*;
+
+/// Domain used to sign records, so that signatures can't be reused in other contexts.
+const RECORD_DOMAIN: &str = "mubelotix-discovery-record";
+const RECORD_PAYLOAD_TYPE: &[u8] = b"/mubelotix-discovery/record";
+
+/// Records can't be signed further in the future than this (in seconds), to tolerate clock drift.
+const MAX_CLOCK_DRIFT: u64 = 10*60;
+
+/// Information a peer certifies about itself.
+/// It is relayed by other peers as a [SignedRecord], which they can't tamper with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    /// The addresses that the peer is listening on.
+    pub listen_addrs: Vec<Multiaddr>,
+
+    /// The list of protocols supported by the peer.
+    pub protocols: Vec<String>,
+
+    /// Unix timestamp (in seconds) of when the record was signed.
+    pub timestamp: u64,
+
+    /// Increases each time the peer signs a new record.
+    /// Records with a lower sequence number than one already known are outdated.
+    pub seq: u64,
+}
+
+/// A [Record] in a libp2p signed envelope (protobuf encoding).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SignedRecord(Vec<u8>);
+
+impl SignedRecord {
+    /// Signs a record with the keypair of the local peer.
+    pub fn sign(keypair: &Keypair, record: &Record) -> Result<SignedRecord, SigningError> {
+        let payload = serde_json::to_vec(record).expect("records are always serializable");
+        let envelope = SignedEnvelope::new(keypair, String::from(RECORD_DOMAIN), RECORD_PAYLOAD_TYPE.to_vec(), payload)?;
+        Ok(SignedRecord(envelope.into_protobuf_encoding()))
+    }
+
+    /// Checks the record was signed by `peer_id` and returns its content.
+    pub fn verify(&self, peer_id: &PeerId) -> Result<Record, InvalidRecord> {
+        let envelope = SignedEnvelope::from_protobuf_encoding(&self.0).map_err(|_| InvalidRecord::Decoding)?;
+        let (payload, public_key) = envelope
+            .payload_and_signing_key(String::from(RECORD_DOMAIN), RECORD_PAYLOAD_TYPE)
+            .map_err(|_| InvalidRecord::Signature)?;
+        if public_key.to_peer_id() != *peer_id {
+            return Err(InvalidRecord::WrongPeer);
+        }
+        let record: Record = serde_json::from_slice(payload).map_err(|_| InvalidRecord::Payload)?;
+        if record.timestamp > now() + MAX_CLOCK_DRIFT {
+            return Err(InvalidRecord::FromFuture);
+        }
+        Ok(record)
+    }
+}
+
+/// Error returned when a [SignedRecord] cannot be trusted.
+#[derive(Debug, Clone)]
+pub enum InvalidRecord {
+    /// The envelope couldn't be decoded.
+    Decoding,
+    /// The signature is invalid or was made for another purpose.
+    Signature,
+    /// The record was signed by another peer than the one it is about.
+    WrongPeer,
+    /// The signed payload isn't a valid record.
+    Payload,
+    /// The record claims to be signed in the future.
+    FromFuture,
+}
+
+impl std::fmt::Display for InvalidRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidRecord::Decoding => write!(f, "invalid envelope"),
+            InvalidRecord::Signature => write!(f, "invalid signature"),
+            InvalidRecord::WrongPeer => write!(f, "record signed by another peer"),
+            InvalidRecord::Payload => write!(f, "invalid record payload"),
+            InvalidRecord::FromFuture => write!(f, "record signed in the future"),
+        }
+    }
+}
+
+/// A peer returned by a discovery query.
+#[derive(Debug, Clone)]
+pub struct DiscoveredPeer {
+    /// Information as relayed by the peer we queried. It can't be trusted.
+    pub info: Info,
+    /// The verified record of the peer, if it signs them.
+    pub record: Option<Record>,
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn record(timestamp: u64, seq: u64) -> Record {
+        Record {
+            listen_addrs: vec!["/ip4/1.2.3.4/tcp/4002".parse().unwrap()],
+            protocols: vec![String::from("/admarus/kamilata/0.2.0")],
+            timestamp,
+            seq,
+        }
+    }
+
+    #[test]
+    fn verify() {
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = keypair.public().to_peer_id();
+        let other_peer_id = Keypair::generate_ed25519().public().to_peer_id();
+
+        // Round trip, through JSON as in responses
+        let signed_record = SignedRecord::sign(&keypair, &record(now(), 5)).unwrap();
+        let signed_record: SignedRecord = serde_json::from_str(&serde_json::to_string(&signed_record).unwrap()).unwrap();
+        let verified = signed_record.verify(&peer_id).unwrap();
+        assert_eq!(verified.seq, 5);
+        assert_eq!(verified.listen_addrs, record(0, 0).listen_addrs);
+
+        // Relayed under another peer id
+        assert!(matches!(signed_record.verify(&other_peer_id), Err(InvalidRecord::WrongPeer)));
+
+        // Tampered envelope
+        let mut tampered = signed_record.clone();
+        let last = tampered.0.len() - 1;
+        tampered.0[last] ^= 1;
+        assert!(matches!(tampered.verify(&peer_id), Err(InvalidRecord::Signature | InvalidRecord::Decoding)));
+        assert!(matches!(SignedRecord(vec![1, 2, 3]).verify(&peer_id), Err(InvalidRecord::Decoding)));
+
+        // Signed in the future
+        let future_record = SignedRecord::sign(&keypair, &record(now() + MAX_CLOCK_DRIFT + 60, 6)).unwrap();
+        assert!(matches!(future_record.verify(&peer_id), Err(InvalidRecord::FromFuture)));
+    }
+}
@@ -15,6 +15,13 @@ pub enum KamilataEvent {
     /// Sent when a leeching task is aborted.
     /// This can happen even if SeederAdded was not sent.
     SeederRemoved { peer_id: PeerId },
+    /// Sent each time a seeder sends us its filters.
+    FiltersReceived { peer_id: PeerId },
+    /// Sent when we are done answering a search request from a peer.
+    SearchServed { peer_id: PeerId, results: usize, duration: Duration },
+    /// Sent when a peer refuses or stops seeding to us.
+    /// We won't request its filters again before `try_again_in` has elapsed (never if None).
+    Disconnected { peer_id: PeerId, reason: String, try_again_in: Option<Duration> },
 }
 
 /// Implementation of the Kamilata protocol.
@@ -145,6 +152,13 @@ impl<const N: usize, S: Store<N>> KamilataBehaviour<N, S> {
         self.handler_event_queue.push((seeder, BehaviorToHandlerEvent::StopSeeding));
     }
 
+    /// Stops seeding to a peer and tells it why.
+    /// The peer is asked not to request our filters again before `try_again_in` seconds (never if None).
+    pub fn evict_leecher(&mut self, leecher: PeerId, reason: impl Into<String>, try_again_in: Option<u32>) {
+        let packet = DisconnectPacket { reason: reason.into(), try_again_in };
+        self.handler_event_queue.push((leecher, BehaviorToHandlerEvent::Disconnect(packet)));
+    }
+
     /// Starts a new search and returns an [handler](OngoingSearchControler) to control it.
     pub async fn search(&mut self, query: impl Into<S::Query>) -> OngoingSearchController<N, S> {
         self.search_with_config(query, SearchConfig::default()).await
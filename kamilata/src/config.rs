@@ -120,6 +120,11 @@ pub struct KamilataConfig {
     /// # let t: ApprocheLeecherClosure = Box::new(approve_leecher);
     /// ```
     pub approve_leecher: Option<ApprocheLeecherClosure>,
+    /// Filter parameters we accept from seeders, by order of preference (default: empty)
+    /// 
+    /// Filters are only combined with filters having the same parameters as the ones of the [Store](crate::store::Store).
+    /// If empty, any parameters fitting in `N` bytes are accepted.
+    pub accepted_filter_params: Vec<FilterParams>,
 }
 
 impl std::fmt::Debug for KamilataConfig {
@@ -130,6 +135,7 @@ impl std::fmt::Debug for KamilataConfig {
             .field("filter_count", &self.filter_count)
             .field("max_seeders", &self.max_seeders)
             .field("max_leechers", &self.max_leechers)
+            .field("accepted_filter_params", &self.accepted_filter_params)
             .field("is_approved_leecher", match self.approve_leecher.is_some() {
                 true => &"Some([closure])",
                 false => &"None",
@@ -147,6 +153,7 @@ impl Default for KamilataConfig {
             max_seeders: 20,
             max_leechers: 50,
             approve_leecher: None,
+            accepted_filter_params: Vec::new(),
         }
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, sync::atomic::{AtomicU64, Ordering}};
 use crate::prelude::*;
 
 pub(crate) struct Db<const N: usize, S: Store<N>> {
@@ -14,6 +14,10 @@ pub(crate) struct Db<const N: usize, S: Store<N>> {
     leechers: RwLock<BTreeSet<PeerId>>,
     /// Known addresses of peers that are connected to us
     addrs: RwLock<BTreeMap<PeerId, Vec<Multiaddr>>>,
+    /// Peers that asked us to leave them alone, until when (None meaning forever)
+    backoffs: RwLock<BTreeMap<PeerId, Option<Instant>>>,
+    /// Incremented each time `seeder_filters` changes
+    filters_generation: AtomicU64,
 }
 
 impl<const N: usize, S: Store<N>> Db<N, S> {
@@ -25,6 +29,8 @@ impl<const N: usize, S: Store<N>> Db<N, S> {
             seeder_filters: RwLock::new(BTreeMap::new()),
             addrs: RwLock::new(BTreeMap::new()),
             leechers: RwLock::new(BTreeSet::new()),
+            backoffs: RwLock::new(BTreeMap::new()),
+            filters_generation: AtomicU64::new(0),
         }
     }
 
@@ -53,9 +59,31 @@ impl<const N: usize, S: Store<N>> Db<N, S> {
         self.addrs.write().await.insert(peer_id, addrs);
     }
 
+    /// Remembers that a peer doesn't want to hear from us for some time.
+    /// This survives disconnections.
+    pub async fn set_backoff(&self, peer_id: PeerId, try_again_in: Option<u32>) {
+        let until = try_again_in.map(|s| Instant::now() + Duration::from_secs(s as u64));
+        self.backoffs.write().await.insert(peer_id, until);
+    }
+
+    /// Returns true if the peer asked us not to request its filters for now.
+    pub async fn is_backed_off(&self, peer_id: &PeerId) -> bool {
+        let mut backoffs = self.backoffs.write().await;
+        match backoffs.get(peer_id) {
+            Some(Some(until)) if *until <= Instant::now() => {
+                backoffs.remove(peer_id);
+                false
+            },
+            Some(_) => true,
+            None => false,
+        }
+    }
+
     /// Remove data about a peer.
     pub async fn remove_peer(&self, peer_id: &PeerId) {
-        self.seeder_filters.write().await.remove(peer_id);
+        if self.seeder_filters.write().await.remove(peer_id).is_some() {
+            self.filters_generation.fetch_add(1, Ordering::Relaxed);
+        }
         self.addrs.write().await.remove(peer_id);
         self.leechers.write().await.remove(peer_id);
     }
@@ -72,10 +100,11 @@ impl<const N: usize, S: Store<N>> Db<N, S> {
     }
 
     /// Claims a spot as a seeder.
+    /// Succeeds if the peer already has one.
     pub async fn add_seeder(&self, peer_id: PeerId) -> Result<(), TooManySeeders> {
         let mut seeder_filters = self.seeder_filters.write().await;
-        if seeder_filters.len() < self.config.max_seeders {
-            seeder_filters.insert(peer_id, Vec::new());
+        if seeder_filters.contains_key(&peer_id) || seeder_filters.len() < self.config.max_seeders {
+            seeder_filters.entry(peer_id).or_default();
             Ok(())
         } else {
             Err(TooManySeeders{})
@@ -85,21 +114,28 @@ impl<const N: usize, S: Store<N>> Db<N, S> {
     pub async fn set_remote_filter(&self, peer_id: PeerId, filters: Vec<Filter<N>>) {
         // TODO size checks
         self.seeder_filters.write().await.insert(peer_id, filters);
+        self.filters_generation.fetch_add(1, Ordering::Relaxed);
     }
 
-    pub(crate) async fn get_filters(&self, ignore_peers: &[PeerId]) -> Vec<Filter<N>> {
-        let mut result = Vec::new();
-        result.push(self.store.get_filter().await); // FIXME: This is slow
+    /// Changes each time filters received from seeders change.
+    /// Results of [Db::get_remote_filters] don't need to be recomputed until then.
+    pub(crate) fn filters_generation(&self) -> u64 {
+        self.filters_generation.load(Ordering::Relaxed)
+    }
 
+    /// Combines filters received from seeders, from distance 1 to the furthest.
+    /// Filters with other parameters than `params` are left out, as they cannot be combined.
+    pub(crate) async fn get_remote_filters(&self, ignore_peers: &[PeerId], params: FilterParams) -> Vec<Filter<N>> {
+        let mut result = Vec::new();
         let filters = self.seeder_filters.read().await;
         for level in 1..10 {
-            let mut filter = Filter::new();
+            let mut filter = Filter::with_params(params);
             let mut is_null = true;
             for (peer_id, filters) in filters.iter() {
                 if ignore_peers.contains(peer_id) {
                     continue;
                 }
-                if let Some(f) = filters.get(level-1) {
+                if let Some(f) = filters.get(level-1).filter(|f| f.params() == params) {
                     filter.bitor_assign_ref(f);
                     is_null = false;
                 }
@@ -113,11 +149,6 @@ impl<const N: usize, S: Store<N>> Db<N, S> {
         result
     }
 
-    pub(crate) async fn get_filters_bytes(&self, ignore_peers: &[PeerId]) -> Vec<Vec<u8>> {
-        let filters = self.get_filters(ignore_peers).await;
-        filters.into_iter().map(|f| <Vec<u8>>::from(&f)).collect()
-    }
-
     /// Adds a new address for a peer.
     pub async fn add_address(&self, peer_id: PeerId, addr: Multiaddr, front: bool) -> Result<(), DisconnectedPeer> {
         let mut addrs = self.addrs.write().await;
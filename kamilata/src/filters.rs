@@ -1,15 +1,84 @@
-#[derive(Debug, Clone)]
-pub struct Filter<const N: usize>(Box<[u8; N]>);
+use unsigned_varint::{encode, decode};
+
+/// Zero bytes needed to end a literal run in [Filter::delta] (shorter gaps are cheaper to copy)
+const MIN_ZERO_RUN: usize = 3;
+
+/// Parameters of a Bloom filter.
+/// Filters can only be combined or compared with filters that have the same parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, protocol::Protocol)]
+pub struct FilterParams {
+    /// Size of the filter in bytes
+    pub size: u32,
+    /// Number of bits set for each word
+    pub hash_count: u8,
+    /// Mixed into hashes, so that filters with different seeds don't share false positives
+    pub seed: u64,
+}
+
+impl FilterParams {
+    pub const fn new(size: usize, hash_count: u8, seed: u64) -> Self {
+        FilterParams { size: size as u32, hash_count, seed }
+    }
+
+    /// Parameters of filters exchanged before parameters were negotiated.
+    pub const fn legacy(size: usize) -> Self {
+        FilterParams::new(size, 1, 0)
+    }
+
+    /// Returns true if filters with these parameters can be stored in a `Filter<N>`.
+    pub fn is_valid<const N: usize>(&self) -> bool {
+        self.size > 0 && self.size as usize <= N && self.hash_count > 0
+    }
+
+    /// Derives `hash_count` bit indices from a single hash, using double hashing.
+    /// The first index is always `hash % bit_len` so that single-hash filters stay compatible.
+    /// This is meant to be used by [Store::hash_word](crate::store::Store::hash_word) implementations.
+    pub fn bit_indices(&self, hash: u64) -> Vec<usize> {
+        let bit_len = self.size as u64 * 8;
+        let step = hash.rotate_left(32) | 1;
+        (0..self.hash_count as u64).map(|i| (hash.wrapping_add(i.wrapping_mul(step)) % bit_len) as usize).collect()
+    }
+}
+
+/// A Bloom filter of at most `N` bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter<const N: usize> {
+    params: FilterParams,
+    bytes: Box<[u8]>,
+}
 
 impl<const N: usize> Filter<N> {
-    /// Creates an empty filter.
+    /// Creates an empty filter of `N` bytes using a single hash.
     pub fn new() -> Self {
-        Filter(Box::new([0; N]))
+        Self::with_params(FilterParams::legacy(N))
+    }
+
+    /// Creates an empty filter with custom parameters.
+    /// Parameters must be [valid](FilterParams::is_valid).
+    pub fn with_params(params: FilterParams) -> Self {
+        debug_assert!(params.is_valid::<N>());
+        Filter {
+            params,
+            bytes: vec![0; (params.size as usize).min(N)].into_boxed_slice(),
+        }
+    }
+
+    /// Creates a filter from its parameters and content.
+    /// Returns None if the parameters are invalid or don't match the content.
+    pub fn from_bytes(params: FilterParams, bytes: &[u8]) -> Option<Self> {
+        if !params.is_valid::<N>() || bytes.len() != params.size as usize {
+            return None;
+        }
+        Some(Filter { params, bytes: bytes.into() })
+    }
+
+    pub fn params(&self) -> FilterParams {
+        self.params
     }
 
     /// Clears the filter.
     pub fn clear(&mut self) {
-        *self = Self::new();
+        self.bytes.iter_mut().for_each(|byte| *byte = 0);
     }
 
     /// Gets a bit in the filter.
@@ -20,14 +89,14 @@ impl<const N: usize> Filter<N> {
         let bit_idx = idx.rem_euclid(8);
         let byte_idx = idx.div_euclid(8);
         let bit = unsafe {
-            (self.0.get_unchecked(byte_idx) >> bit_idx) & 1
+            (self.bytes.get_unchecked(byte_idx) >> bit_idx) & 1
         };
         bit != 0
     }
 
     /// Gets a word in the filter.
     pub fn get_word<S: crate::store::Store<N>>(&self, word: &str) -> bool {
-        S::hash_word(word).into_iter().all(|hash| self.get_bit(hash))
+        S::hash_word(word, &self.params).into_iter().all(|hash| self.get_bit(hash))
     }
 
     /// Sets a bit in the filter.
@@ -40,18 +109,18 @@ impl<const N: usize> Filter<N> {
         let bit = value as u8;
         let keeping_mask = !(1 << bit_idx);
         unsafe {
-            *self.0.get_unchecked_mut(byte_idx) = (self.0.get_unchecked(byte_idx) & keeping_mask) + (bit << bit_idx);
+            *self.bytes.get_unchecked_mut(byte_idx) = (self.bytes.get_unchecked(byte_idx) & keeping_mask) + (bit << bit_idx);
         }
     }
 
     /// Adds a word in the filter.
     pub fn add_word<S: crate::store::Store<N>>(&mut self, word: &str) {
-        S::hash_word(word).into_iter().for_each(|hash| self.set_bit(hash, true));
+        S::hash_word(word, &self.params).into_iter().for_each(|hash| self.set_bit(hash, true));
     }
 
     /// Returns the number of bits set to 1 in the filter.
     pub fn count_set_bits(&self) -> usize {
-        self.0.iter().map(|byte| byte.count_ones() as usize).sum()
+        self.bytes.iter().map(|byte| byte.count_ones() as usize).sum()
     }
 
     /// Returns the proportion of bits that are set to 1.
@@ -60,21 +129,77 @@ impl<const N: usize> Filter<N> {
     }
 
     /// Returns the number of bytes in the filter.
-    pub const fn len(&self) -> usize {
-        N
+    pub fn len(&self) -> usize {
+        self.bytes.len()
     }
 
     /// Returns the number of bits in the filter.
-    pub const fn bit_len(&self) -> usize {
-        N*8
+    pub fn bit_len(&self) -> usize {
+        self.bytes.len()*8
     }
 
     /// Returns true if the filter is empty.
     pub fn is_empty(&self) -> bool {
-        self.0.iter().all(|byte| *byte == 0)
+        self.bytes.iter().all(|byte| *byte == 0)
     }
 }
 
+impl<const N: usize> Filter<N> {
+    /// Encodes the changes from `previous` to this filter.
+    /// 
+    /// The XOR of both filters is run-length encoded as a sequence of `(zero_count, literal_count, literal_bytes)`.
+    /// As filters are mostly stable, the XOR is mostly zeroes and deltas are tiny.
+    /// Both filters should have the same parameters.
+    pub fn delta(&self, previous: &Self) -> Vec<u8> {
+        let xor = self.bytes.iter().enumerate().map(|(i, a)| a ^ previous.bytes.get(i).copied().unwrap_or(0)).collect::<Vec<u8>>();
+        let len = xor.len();
+        let mut delta = Vec::new();
+        let mut buffer = encode::usize_buffer();
+        let mut i = 0;
+        while i < len {
+            let zeroes_start = i;
+            while i < len && xor[i] == 0 {
+                i += 1;
+            }
+            if i == len {
+                break;
+            }
+            let literal_start = i;
+            while i < len && !xor[i..len.min(i + MIN_ZERO_RUN)].iter().all(|b| *b == 0) {
+                i += 1;
+            }
+            delta.extend_from_slice(encode::usize(literal_start - zeroes_start, &mut buffer));
+            delta.extend_from_slice(encode::usize(i - literal_start, &mut buffer));
+            delta.extend_from_slice(&xor[literal_start..i]);
+        }
+        delta
+    }
+
+    /// Applies a delta produced by [Filter::delta] on the previous version of the filter.
+    pub fn apply_delta(&mut self, mut delta: &[u8]) -> Result<(), InvalidDelta> {
+        let mut i: usize = 0;
+        while !delta.is_empty() {
+            let (zeroes, rest) = decode::usize(delta).map_err(|_| InvalidDelta)?;
+            let (literals, rest) = decode::usize(rest).map_err(|_| InvalidDelta)?;
+            i = i.checked_add(zeroes).ok_or(InvalidDelta)?;
+            let end = i.checked_add(literals).ok_or(InvalidDelta)?;
+            if end > self.bytes.len() || rest.len() < literals {
+                return Err(InvalidDelta);
+            }
+            for (byte, change) in self.bytes[i..end].iter_mut().zip(&rest[..literals]) {
+                *byte ^= change;
+            }
+            i = end;
+            delta = &rest[literals..];
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when a delta doesn't fit the filter it is applied on.
+#[derive(Debug, Clone)]
+pub struct InvalidDelta;
+
 impl<const N: usize> Default for Filter<N> {
     fn default() -> Self {
         Self::new()
@@ -85,21 +210,16 @@ impl<const N: usize> std::ops::BitOr for Filter<N> {
     type Output = Self;
 
     fn bitor(mut self, other: Self) -> Self::Output {
-        for byte_idx in 0..N {
-            unsafe {
-                *self.0.get_unchecked_mut(byte_idx) |= *other.0.get_unchecked(byte_idx);
-            }
-        }
+        self.bitor_assign_ref(&other);
         self
     }
 }
 
 impl<const N: usize> Filter<N> {
+    /// Both filters should have the same parameters.
     pub fn bitor_assign_ref(&mut self, other: &Self) {
-        for byte_idx in 0..N {
-            unsafe {
-                *self.0.get_unchecked_mut(byte_idx) |= *other.0.get_unchecked(byte_idx);
-            }
+        for (byte, other_byte) in self.bytes.iter_mut().zip(other.bytes.iter()) {
+            *byte |= other_byte;
         }
     }
 }
@@ -110,22 +230,9 @@ impl<const N: usize> std::ops::BitOrAssign for Filter<N> {
     }
 }
 
-impl<const N: usize> From<&[u8]> for Filter<N> {
-    fn from(bytes: &[u8]) -> Self {
-        let mut filter = Filter::new();
-        for byte_idx in 0..N {
-            // TODO check that bytes.len() == N
-            unsafe {
-                *filter.0.get_unchecked_mut(byte_idx) = *bytes.get_unchecked(byte_idx);
-            }
-        }
-        filter
-    }
-}
-
 impl<const N: usize> From<&Filter<N>> for Vec<u8> {
     fn from(filter: &Filter<N>) -> Self {
-        filter.0.to_vec()
+        filter.bytes.to_vec()
     }
 }
 
@@ -163,4 +270,42 @@ mod tests {
         let filter3 = filter1 | filter2;
         assert_eq!(filter3.count_set_bits(), 2);
     }
+
+    #[test]
+    fn deltas() {
+        let mut previous = Filter::<64>::new();
+        previous.set_bit(3, true);
+        previous.set_bit(200, true);
+        let mut filter = previous.clone();
+        assert!(filter.delta(&previous).is_empty());
+
+        filter.set_bit(3, false);
+        filter.set_bit(40, true);
+        filter.set_bit(50, true);
+        filter.set_bit(511, true);
+        let delta = filter.delta(&previous);
+        assert!(delta.len() < 16);
+
+        let mut rebuilt = previous.clone();
+        rebuilt.apply_delta(&delta).unwrap();
+        assert_eq!(rebuilt, filter);
+        assert!(previous.apply_delta(&[70, 1, 0]).is_err());
+    }
+
+    #[test]
+    fn params() {
+        let params = FilterParams::new(16, 3, 0);
+        assert!(params.is_valid::<16>());
+        assert!(!params.is_valid::<8>());
+
+        let indices = params.bit_indices(1234567);
+        assert_eq!(indices.len(), 3);
+        assert_eq!(indices[0], 1234567 % 128);
+        assert!(indices.iter().all(|i| *i < 128));
+
+        let filter = Filter::<16>::with_params(FilterParams::legacy(8));
+        assert_eq!(filter.len(), 8);
+        assert!(Filter::<16>::from_bytes(FilterParams::legacy(8), &[0; 4]).is_none());
+        assert!(Filter::<16>::from_bytes(FilterParams::legacy(8), &[0; 8]).is_some());
+    }
 }
@@ -22,6 +22,8 @@ pub enum BehaviorToHandlerEvent<const N: usize, S: Store<N>> {
     StopLeeching,
     /// Asks the handler to stop seeding
     StopSeeding,
+    /// Asks the handler to stop seeding and send the packet to the peer
+    Disconnect(DisconnectPacket),
 }
 
 impl<const N: usize, S: Store<N>> std::fmt::Debug for BehaviorToHandlerEvent<N, S> {
@@ -32,6 +34,7 @@ impl<const N: usize, S: Store<N>> std::fmt::Debug for BehaviorToHandlerEvent<N,
             BehaviorToHandlerEvent::LeechFilters => write!(f, "LeechFilters"),
             BehaviorToHandlerEvent::StopLeeching => write!(f, "StopLeeching"),
             BehaviorToHandlerEvent::StopSeeding => write!(f, "StopSeeding"),
+            BehaviorToHandlerEvent::Disconnect(_) => write!(f, "Disconnect"),
         }
     }
 }
@@ -77,6 +80,26 @@ impl<const N: usize, S: Store<N>> KamilataHandler<N, S> {
     }
 }
 
+impl<const N: usize, S: Store<N>> KamilataHandler<N, S> {
+    fn stop_seeding(&mut self) {
+        if self.tasks.remove(&1).is_some() {
+            let behaviour_controller = self.db.behaviour_controller().clone();
+            let remote_peer_id = self.remote_peer_id;
+            tokio::spawn(async move {
+                behaviour_controller.emit_event(KamilataEvent::LeecherRemoved { peer_id: remote_peer_id }).await;
+            });
+        }
+    }
+
+    /// Sends a [RequestPacket::Disconnect] through a new outbound substream.
+    fn send_disconnect(&mut self, packet: DisconnectPacket) {
+        debug!("{} Disconnecting {}: {} (try again in {:?}s)", self.our_peer_id, self.remote_peer_id, packet.reason, packet.try_again_in);
+        let (sender, _) = oneshot_channel();
+        let pending_task = pending_request::<N>(RequestPacket::Disconnect(packet), sender, self.our_peer_id, self.remote_peer_id);
+        self.pending_tasks.push((None, pending_task));
+    }
+}
+
 impl<const N: usize, S: Store<N>> ConnectionHandler for KamilataHandler<N, S> {
     type FromBehaviour = BehaviorToHandlerEvent<N, S>;
     type ToBehaviour = HandlerToBehaviorEvent;
@@ -119,14 +142,10 @@ impl<const N: usize, S: Store<N>> ConnectionHandler for KamilataHandler<N, S> {
                     });
                 }
             },
-            BehaviorToHandlerEvent::StopSeeding => {
-                if self.tasks.remove(&1).is_some() {
-                    let behaviour_controller = self.db.behaviour_controller().clone();
-                    let remote_peer_id = self.remote_peer_id;
-                    tokio::spawn(async move {
-                        behaviour_controller.emit_event(KamilataEvent::LeecherRemoved { peer_id: remote_peer_id }).await;
-                    });
-                }
+            BehaviorToHandlerEvent::StopSeeding => self.stop_seeding(),
+            BehaviorToHandlerEvent::Disconnect(packet) => {
+                self.stop_seeding();
+                self.send_disconnect(packet);
             },
         };
     }
@@ -238,8 +257,7 @@ impl<const N: usize, S: Store<N>> ConnectionHandler for KamilataHandler<N, S> {
                                 self.pending_tasks.push((tid, pending_task));
                             },
                             HandlerTaskOutput::Disconnect(disconnect_packet) => {
-                                debug!("{} Disconnected peer {}", self.our_peer_id, self.remote_peer_id);
-                                // TODO: send packet
+                                self.send_disconnect(disconnect_packet);
                                 self.keep_alive = false;
                             },
                             HandlerTaskOutput::None | HandlerTaskOutput::Many(_) => unreachable!(),
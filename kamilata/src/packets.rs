@@ -1,5 +1,5 @@
 use protocol_derive::Protocol;
-use crate::config::MinTargetMax;
+use crate::{config::MinTargetMax, filters::FilterParams};
 
 #[derive(Clone)]
 #[repr(transparent)]
@@ -62,13 +62,14 @@ impl HackTraitVecPeerId for Vec<PeerId> {
 
 #[derive(Protocol, Debug, Clone)]
 pub enum RequestPacket {
-    /// Request the peer to send us its filters.
-    /// The peer accepts by continuously sending [ResponsePacket::UpdateFilters], or closes the channel.
-    GetFilters(GetFiltersPacket),
     /// Asks to apply our query on its documents and return results in the [ResponsePacket::ReturnResults] packet.
     Search(SearchPacket),
-
+    /// Informs the peer we stopped seeding to it, and when it may try again.
+    /// No response is expected.
     Disconnect(DisconnectPacket),
+    /// Request the peer to send us its filters.
+    /// The peer accepts by continuously sending [ResponsePacket::UpdateFilterDeltas], or refuses with [ResponsePacket::Disconnect].
+    GetFilterUpdates(GetFiltersPacket),
 }
 
 #[derive(Protocol, Debug, Clone)]
@@ -79,6 +80,8 @@ pub struct GetFiltersPacket {
     pub interval: MinTargetMax,
     /// Peers we don't want to hear from
     pub blocked_peers: Vec<PeerId>,
+    /// Filter parameters we accept, by order of preference (any if empty)
+    pub filter_params: Vec<FilterParams>,
 }
 
 impl Default for GetFiltersPacket {
@@ -91,6 +94,7 @@ impl Default for GetFiltersPacket {
                 max: 60_000,
             },
             blocked_peers: Vec::new(),
+            filter_params: Vec::new(),
         }
     }
 }
@@ -113,8 +117,6 @@ pub struct SearchPacket {
 
 #[derive(Protocol, Debug, Clone)]
 pub enum ResponsePacket {
-    /// Sent periodically to inform the peers of our filters.
-    UpdateFilters(UpdateFiltersPacket),
     /// Response to a [RequestPacket::Search] packet.
     /// Will be followed by multiple [ResponsePacket::Result].
     Routes(RoutesPacket),
@@ -122,14 +124,32 @@ pub enum ResponsePacket {
     Result(ResultPacket),
     /// Sent once all [ResponsePacket::Result] have been sent.
     SearchOver,
-
+    /// Response to a [RequestPacket::GetFilterUpdates] packet we refuse to serve.
+    /// The channel is closed right after.
     Disconnect(DisconnectPacket),
+    /// Sent periodically to inform the peers of changes in our filters.
+    UpdateFilterDeltas(UpdateFilterDeltasPacket),
+}
+
+#[derive(Protocol, Debug, Clone)]
+pub struct UpdateFilterDeltasPacket {
+    /// Starts at 1 and is incremented with each packet sent on the channel.
+    pub version: u64,
+    /// Parameters of all the filters.
+    /// When they change, previous filters are to be considered empty.
+    pub params: FilterParams,
+    /// The changes to each filter since the previous version, ordered from distance 0.
+    /// Filters further than the length of this list are removed.
+    pub filters: Vec<FilterDelta>,
 }
 
 #[derive(Protocol, Debug, Clone)]
-pub struct UpdateFiltersPacket {
-    /// The filters ordered from distance 0 to the furthest at a distance of [RefreshPacket::range].
-    pub filters: Vec<Vec<u8>>,
+pub enum FilterDelta {
+    /// The filter is the same as in the previous version.
+    Unchanged,
+    /// The filter changed, or is new (in which case the previous version is an empty filter).
+    /// Contains the output of [Filter::delta](crate::filters::Filter::delta).
+    Changed(Vec<u8>),
 }
 
 #[derive(Protocol, Debug, Clone)]
@@ -157,7 +177,7 @@ pub struct ResultPacket(
 pub struct DisconnectPacket {
     /// The reason for the disconnection.
     pub reason: String,
-    /// Asks the peer to reconnect after a certain amount of time.
+    /// Asks the peer to reconnect after a certain amount of time (in seconds).
     /// None if we never want to hear about that peer again.
     pub try_again_in: Option<u32>,
 }
@@ -20,15 +20,17 @@ pub trait Store<const N: usize>: Send + Sync + 'static {
     type Result: SearchResult + Send + Sync;
     type Query: SearchQuery<N>;
     
-    /// Hash a word the way you like.
-    /// You can return multiple hashes for a single input (it's the idea behind Bloom filters).
+    /// Hash a word the way you like, for a filter with the given parameters.
+    /// You should return `params.hash_count` hashes, and take `params.seed` into account.
+    /// [FilterParams::bit_indices] can derive them from a single hash.
     /// 
     /// Must return at least one value.
-    /// Must return values lower than `N*8` as they will be used as bit indices in filters.
-    fn hash_word(word: &str) -> Vec<usize>;
+    /// Must return values lower than `params.size*8` as they will be used as bit indices in filters.
+    fn hash_word(word: &str, params: &FilterParams) -> Vec<usize>;
 
     /// Return a filter that has been filled with the words of the documents.
     /// This function is intented to return a cached value as the filter should have been generated earlier.
+    /// Its parameters are the ones announced to leechers.
     async fn get_filter(&self) -> Filter<N>; // TODO: use reference?
 
     /// Search among all documents and return those matching at least `min_matching` words.
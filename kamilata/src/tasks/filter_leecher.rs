@@ -5,6 +5,12 @@ use super::*;
 pub(crate) async fn leech_filters<const N: usize, S: Store<N>>(mut stream: KamOutStreamSink<Stream>, db: Arc<Db<N, S>>, our_peer_id: PeerId, remote_peer_id: PeerId) -> HandlerTaskOutput {
     trace!("{our_peer_id} Inbound filter refresh task executing");
 
+    // Honours the delay the peer asked for
+    if db.is_backed_off(&remote_peer_id).await {
+        debug!("{our_peer_id} {remote_peer_id} asked us not to leech for now");
+        return HandlerTaskOutput::None;
+    }
+
     // Claims a spot as a seeder for the remote peer
     if let Err(TooManySeeders{}) = db.add_seeder(remote_peer_id).await {
         warn!("{our_peer_id} Too many seeders, can't leech from {remote_peer_id}");
@@ -13,12 +19,13 @@ pub(crate) async fn leech_filters<const N: usize, S: Store<N>>(mut stream: KamOu
 
     // Send our request
     let config = db.get_config();
-    let req = GetFiltersPacket {
+    let req = RequestPacket::GetFilterUpdates(GetFiltersPacket {
         filter_count: config.filter_count as u8,
         interval: config.get_filters_interval.clone(),
         blocked_peers: Vec::new(), // TODO
-    };
-    if let Err(e) = stream.start_send_unpin(RequestPacket::GetFilters(req)) {
+        filter_params: config.accepted_filter_params.clone(),
+    });
+    if let Err(e) = stream.start_send_unpin(req) {
         warn!("{our_peer_id} Error while sending get filters request to {remote_peer_id}: {e}");
         return HandlerTaskOutput::None;
     }
@@ -33,6 +40,8 @@ pub(crate) async fn leech_filters<const N: usize, S: Store<N>>(mut stream: KamOu
     }).await;
 
     // Receive filters
+    let mut filters: Vec<Filter<N>> = Vec::new();
+    let mut version = 0;
     loop {
         let packet = match stream.next().await {
             Some(Ok(packet)) => packet,
@@ -45,20 +54,73 @@ pub(crate) async fn leech_filters<const N: usize, S: Store<N>>(mut stream: KamOu
                 return HandlerTaskOutput::None;
             }
         };
-        let packet = match packet {
-            ResponsePacket::UpdateFilters(packet) => packet,
+        match packet {
+            ResponsePacket::UpdateFilterDeltas(packet) => {
+                if packet.version != version + 1 {
+                    warn!("{our_peer_id} Received filters version {} from {remote_peer_id} while expecting {}", packet.version, version + 1);
+                    return HandlerTaskOutput::None;
+                }
+                version = packet.version;
+                let accepted = &config.accepted_filter_params;
+                if !packet.params.is_valid::<N>() || (!accepted.is_empty() && !accepted.contains(&packet.params)) {
+                    warn!("{our_peer_id} Received filters with unaccepted parameters from {remote_peer_id} ({:?})", packet.params);
+                    return HandlerTaskOutput::None;
+                }
+                if filters.first().map(|f| f.params() != packet.params).unwrap_or(false) {
+                    filters.clear();
+                }
+                let mut changed = filters.len() != packet.filters.len();
+                filters.truncate(packet.filters.len());
+                for (level, delta) in packet.filters.into_iter().enumerate() {
+                    match delta {
+                        FilterDelta::Unchanged if level < filters.len() => (),
+                        FilterDelta::Unchanged => {
+                            warn!("{our_peer_id} {remote_peer_id} claimed unknown filter at distance {level} is unchanged");
+                            return HandlerTaskOutput::None;
+                        },
+                        FilterDelta::Changed(delta) => {
+                            if level == filters.len() {
+                                filters.push(Filter::with_params(packet.params));
+                            }
+                            if filters[level].apply_delta(&delta).is_err() {
+                                warn!("{our_peer_id} Received invalid filter delta from {remote_peer_id}");
+                                return HandlerTaskOutput::None;
+                            }
+                            changed = true;
+                        },
+                    }
+                }
+                if changed {
+                    db.set_remote_filter(remote_peer_id, filters.clone()).await;
+                }
+            },
+            ResponsePacket::Disconnect(packet) => {
+                debug!("{our_peer_id} {remote_peer_id} stopped seeding to us: {}", packet.reason);
+                on_disconnect_packet(&db, remote_peer_id, packet).await;
+                return HandlerTaskOutput::None;
+            },
             _ => {
                 warn!("{our_peer_id} Received unexpected packet from {remote_peer_id} while waiting for filters");
                 return HandlerTaskOutput::None;
             },
-        };
-        // TODO check packet.filters lenght and count and time between received
-        let filters = packet.filters.iter().map(|f| f.as_slice().into()).collect::<Vec<Filter<N>>>();
-        db.set_remote_filter(remote_peer_id, filters).await;
+        }
         trace!("{our_peer_id} Received filters from {remote_peer_id}");
+        db.behaviour_controller().emit_event(KamilataEvent::FiltersReceived {
+            peer_id: remote_peer_id,
+        }).await;
     }
 }
 
+/// Records the back-off requested by the peer and notifies the behaviour.
+pub(crate) async fn on_disconnect_packet<const N: usize, S: Store<N>>(db: &Db<N, S>, remote_peer_id: PeerId, packet: DisconnectPacket) {
+    db.set_backoff(remote_peer_id, packet.try_again_in).await;
+    db.behaviour_controller().emit_event(KamilataEvent::Disconnected {
+        peer_id: remote_peer_id,
+        reason: packet.reason,
+        try_again_in: packet.try_again_in.map(|s| Duration::from_secs(s as u64)),
+    }).await;
+}
+
 pub(crate) fn leech_filters_boxed<const N: usize, S: Store<N>>(stream: KamOutStreamSink<Stream>, vals: Box<dyn Any + Send>) -> Pin<Box<dyn Future<Output = HandlerTaskOutput> + Send>> {
     let vals: Box<(Arc<Db<N, S>>, PeerId, PeerId)> = vals.downcast().unwrap(); // TODO: downcast unchecked?
     leech_filters(stream, vals.0, vals.1, vals.2).boxed()
@@ -2,8 +2,19 @@
 
 use super::*;
 
-// TODO: When rejecting a peer, we should send a message to the peer explaining why we rejected it
+/// Seconds after which a peer we refused for lack of room may try again
+const REFUSED_LEECHER_RETRY_DELAY: u32 = 10*60;
 
+/// Tells the peer why we are refusing to seed to it before the stream gets closed.
+async fn refuse(stream: &mut KamInStreamSink<Stream>, reason: &str, try_again_in: Option<u32>) {
+    let packet = DisconnectPacket { reason: reason.to_string(), try_again_in };
+    if stream.start_send_unpin(ResponsePacket::Disconnect(packet)).is_ok() {
+        let _ = stream.flush().await;
+    }
+}
+
+/// Sends our filters to a leecher.
+/// Only changes are sent, as [ResponsePacket::UpdateFilterDeltas].
 pub(crate) async fn seed_filters<const N: usize, S: Store<N>>(
     mut stream: KamInStreamSink<Stream>,
     mut req: GetFiltersPacket,
@@ -13,10 +24,19 @@ pub(crate) async fn seed_filters<const N: usize, S: Store<N>>(
 ) -> HandlerTaskOutput {
     trace!("{our_peer_id} Seed filters task executing");
 
+    // Checks the peer can use our filters
+    let params = db.store().get_filter().await.params();
+    if !req.filter_params.is_empty() && !req.filter_params.contains(&params) {
+        warn!("{our_peer_id} {remote_peer_id} doesn't accept our filter parameters ({params:?})");
+        refuse(&mut stream, "Incompatible filter parameters", None).await;
+        return HandlerTaskOutput::None;
+    }
+
     // Checks if we should allow this peer to leech
     if let Some(approve_leecher) = &db.get_config().approve_leecher {
         if !approve_leecher(remote_peer_id).await {
             warn!("{our_peer_id} {remote_peer_id} wasn't approved to leech");
+            refuse(&mut stream, "Not approved to leech", Some(REFUSED_LEECHER_RETRY_DELAY)).await;
             return HandlerTaskOutput::None;
         }
     }
@@ -24,6 +44,7 @@ pub(crate) async fn seed_filters<const N: usize, S: Store<N>>(
     // Claims a spot as a leecher for the remote peer
     if let Err(TooManyLeechers{}) = db.add_leecher(remote_peer_id).await {
         warn!("{our_peer_id} Too many leechers, can't seed to {remote_peer_id}");
+        refuse(&mut stream, "Too many leechers", Some(REFUSED_LEECHER_RETRY_DELAY)).await;
         return HandlerTaskOutput::None;
     }
 
@@ -34,6 +55,7 @@ pub(crate) async fn seed_filters<const N: usize, S: Store<N>>(
         Some(interval) => interval.target() as u64,
         None => {
             warn!("{our_peer_id} Couldn't agree on interval with {remote_peer_id} (ours: {:?}, theirs: {:?})", config.get_filters_interval, req.interval);
+            refuse(&mut stream, "Incompatible filter interval", None).await;
             return HandlerTaskOutput::None;
         }
     };
@@ -48,9 +70,46 @@ pub(crate) async fn seed_filters<const N: usize, S: Store<N>>(
     let mut peers_to_ignore = req.blocked_peers.to_libp2p_peer_ids();
     peers_to_ignore.push(remote_peer_id);
 
+    let mut remote_filters = Vec::new();
+    let mut remote_filters_generation = None;
+    let mut sent_filters: Vec<Filter<N>> = Vec::new();
+    let mut sent_params = params;
+    let mut version = 0;
     loop {
-        let our_filters = db.get_filters_bytes(&peers_to_ignore).await; // FIXME: filter count isn't respected
-        stream.start_send_unpin(ResponsePacket::UpdateFilters(UpdateFiltersPacket { filters: our_filters })).unwrap();
+        let local_filter = db.store().get_filter().await;
+        let params = local_filter.params();
+        if params != sent_params {
+            sent_filters.clear();
+            remote_filters_generation = None;
+            sent_params = params;
+        }
+
+        // Filters of seeders are only combined again when they changed
+        let generation = db.filters_generation();
+        if remote_filters_generation != Some(generation) {
+            remote_filters = db.get_remote_filters(&peers_to_ignore, params).await;
+            remote_filters_generation = Some(generation);
+        }
+        let our_filters = std::iter::once(&local_filter).chain(remote_filters.iter()).take(req.filter_count as usize);
+
+        version += 1;
+        let mut deltas = Vec::new();
+        for (level, filter) in our_filters.enumerate() {
+            match sent_filters.get_mut(level) {
+                Some(sent_filter) if sent_filter == filter => deltas.push(FilterDelta::Unchanged),
+                Some(sent_filter) => {
+                    deltas.push(FilterDelta::Changed(filter.delta(sent_filter)));
+                    *sent_filter = filter.clone();
+                },
+                None => {
+                    deltas.push(FilterDelta::Changed(filter.delta(&Filter::with_params(params))));
+                    sent_filters.push(filter.clone());
+                },
+            }
+        }
+        sent_filters.truncate(deltas.len());
+        let packet = ResponsePacket::UpdateFilterDeltas(UpdateFilterDeltasPacket { version, params, filters: deltas });
+        stream.start_send_unpin(packet).unwrap();
         if stream.flush().await.is_err() {
             warn!("{our_peer_id} Couldn't send filters to {remote_peer_id}");
             return HandlerTaskOutput::None;
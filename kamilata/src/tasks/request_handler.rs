@@ -19,7 +19,7 @@ pub(crate) async fn handle_request<const N: usize, S: Store<N>>(
 
     debug!("{our_peer_id} Request from {remote_peer_id}: {request:?}");
     match request {
-        RequestPacket::GetFilters(refresh_packet) => {
+        RequestPacket::GetFilterUpdates(refresh_packet) => {
             let task = seed_filters(stream, refresh_packet, db, our_peer_id, remote_peer_id);
             HandlerTaskOutput::SetTask {
                 tid: 1,
@@ -27,6 +27,7 @@ pub(crate) async fn handle_request<const N: usize, S: Store<N>>(
             }
         },
         RequestPacket::Search(search_packet) => {
+            let start = Instant::now();
             let query = match S::Query::from_bytes(&search_packet.query) {
                 Ok(query) => Arc::new(query),
                 Err(e) => {
@@ -53,8 +54,9 @@ pub(crate) async fn handle_request<const N: usize, S: Store<N>>(
 
             // Get results
             let (sender, mut receiver) = channel::<S::Result>(100);
+            let db2 = Arc::clone(&db);
             spawn(async move {
-                let fut = db.store().search(Arc::clone(&query));
+                let fut = db2.store().search(Arc::clone(&query));
                 let mut result_stream = fut.await;
                 while let Some(result) = result_stream.next().await {
                     let Ok(()) = sender.send(result).await else {break};
@@ -62,17 +64,28 @@ pub(crate) async fn handle_request<const N: usize, S: Store<N>>(
             });
 
             // Send results
+            let mut results = 0;
             while let Some(result) = receiver.recv().await {
                 let Ok(()) = stream.start_send_unpin(ResponsePacket::Result(ResultPacket(result.into_bytes()))) else {break};
                 let Ok(()) = stream.flush().await else {break};
+                results += 1;
             }
 
             // Send search over
             let Ok(()) = stream.start_send_unpin(ResponsePacket::SearchOver) else {return HandlerTaskOutput::None};
             let Ok(()) = stream.flush().await else {return HandlerTaskOutput::None};
+            db.behaviour_controller().emit_event(KamilataEvent::SearchServed {
+                peer_id: remote_peer_id,
+                results,
+                duration: start.elapsed(),
+            }).await;
 
             HandlerTaskOutput::None
         },
-        RequestPacket::Disconnect(_) => todo!(),
+        RequestPacket::Disconnect(packet) => {
+            debug!("{our_peer_id} {remote_peer_id} stopped seeding to us: {}", packet.reason);
+            on_disconnect_packet(&db, remote_peer_id, packet).await;
+            HandlerTaskOutput::None
+        },
     }
 }
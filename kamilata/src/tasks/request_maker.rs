@@ -10,6 +10,7 @@ pub async fn request<const N: usize>(
     remote_peer_id: PeerId
 ) -> HandlerTaskOutput {
     trace!("{our_peer_id} Sending request to {remote_peer_id}: {request:?}");
+    let expects_response = !matches!(request, RequestPacket::Disconnect(_));
 
     // Send request packet
     match stream.start_send_unpin(request) {
@@ -25,6 +26,10 @@ pub async fn request<const N: usize>(
         let _ = sender.send(None);
         return HandlerTaskOutput::None;
     }
+    if !expects_response {
+        let _ = sender.send(None);
+        return HandlerTaskOutput::None;
+    }
 
     // Receive response packet
     let packet = match stream.next().await {
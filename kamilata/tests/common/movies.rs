@@ -85,8 +85,8 @@ impl<const N: usize> Store<N> for MovieIndex<N> {
     type Result = Movie;
     type Query = MovieQuery;
 
-    fn hash_word(word: &str) -> Vec<usize> {
-        let mut result = 1usize;
+    fn hash_word(word: &str, params: &FilterParams) -> Vec<usize> {
+        let mut result = 1usize ^ params.seed as usize;
         const RANDOM_SEED: [usize; 16] = [542587211452, 5242354514, 245421154, 4534542154, 542866467, 545245414, 7867569786914, 88797854597, 24542187316, 645785447, 434963879, 4234274, 55418648642, 69454242114688, 74539841, 454214578213];
         for c in word.bytes() {
             for i in 0..8 {
@@ -94,7 +94,7 @@ impl<const N: usize> Store<N> for MovieIndex<N> {
                 result = result.overflowing_add(c as usize + RANDOM_SEED[i*2+1]).0;
             }
         }
-        vec![result % (N * 8)]
+        params.bit_indices(result as u64)
     }
 
     async fn get_filter(&self) -> Filter<N> {
@@ -12,6 +12,7 @@ async fn limits() -> Result<(), Box<dyn std::error::Error>> {
         get_filters_interval: MinTargetMax::new(60_000_000, 60_000_000, 60_000_000),
         filter_count: 0,
         approve_leecher: None,
+        accepted_filter_params: Vec::new(),
     };
 
     info!("Initializing clients...");
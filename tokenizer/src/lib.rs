@@ -0,0 +1,249 @@
+//! Splits text into normalized words.
+//!
+//! This is shared by everything that produces or consumes words: indexing, query parsing and ranking.
+//! Providers and clients must agree on how a word is written, otherwise Bloom filter lookups and term counts won't match.
+//! Any change to the output of this crate is thus a breaking change of the protocol.
+
+use rust_stemmers::{Algorithm, Stemmer};
+use std::ops::Range;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Words shorter than this are ignored, unless they are written in a CJK script.
+pub const MIN_WORD_LEN: usize = 3;
+
+/// A normalized word along with its position in the original text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub word: String,
+    /// Byte range of the word in the original text
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tokenizer {
+    /// Chinese and Japanese text isn't separated by spaces.
+    /// When enabled, runs of ideographs are indexed as overlapping pairs instead of single characters.
+    pub cjk_bigrams: bool,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Tokenizer {
+            cjk_bigrams: true,
+        }
+    }
+}
+
+impl Tokenizer {
+    pub fn tokens(&self, text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut cjk_run: Vec<(usize, char)> = Vec::new();
+
+        for (start, segment) in text.unicode_word_indices() {
+            // Single ideographs are accumulated as long as they are contiguous
+            let mut chars = segment.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                if self.cjk_bigrams && is_ideograph(c) {
+                    if cjk_run.last().map(|(i, c)| i + c.len_utf8() != start).unwrap_or(false) {
+                        self.flush_cjk_run(&mut cjk_run, &mut tokens);
+                    }
+                    cjk_run.push((start, c));
+                    continue;
+                }
+            }
+            self.flush_cjk_run(&mut cjk_run, &mut tokens);
+
+            // Elisions such as "l'été" are split so that "été" can be found
+            let mut offset = start;
+            for part in segment.split_inclusive(['\'', '’']) {
+                let word = part.trim_end_matches(['\'', '’']);
+                let range = offset..offset + word.len();
+                offset += part.len();
+                let word = normalize(word);
+                if word.chars().count() >= MIN_WORD_LEN || word.chars().any(is_cjk) {
+                    tokens.push(Token { word, range });
+                }
+            }
+        }
+        self.flush_cjk_run(&mut cjk_run, &mut tokens);
+
+        tokens
+    }
+
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        self.tokens(text).into_iter().map(|t| t.word).collect()
+    }
+
+    fn flush_cjk_run(&self, run: &mut Vec<(usize, char)>, tokens: &mut Vec<Token>) {
+        match run.len() {
+            0 => (),
+            1 => {
+                let (start, c) = run[0];
+                tokens.push(Token { word: normalize(&c.to_string()), range: start..start + c.len_utf8() });
+            },
+            _ => {
+                for pair in run.windows(2) {
+                    let (start, c1) = pair[0];
+                    let (end, c2) = pair[1];
+                    let word = normalize(&format!("{c1}{c2}"));
+                    tokens.push(Token { word, range: start..end + c2.len_utf8() });
+                }
+            },
+        }
+        run.clear();
+    }
+}
+
+/// Folds case and diacritics so that "Café", "CAFE" and "café" are the same word.
+pub fn normalize(word: &str) -> String {
+    let mut normalized = String::with_capacity(word.len());
+    for c in word.nfkd().filter(|c| !is_diacritic(*c)).nfc() {
+        match c {
+            'ß' | 'ẞ' => normalized.push_str("ss"),
+            'Æ' | 'æ' => normalized.push_str("ae"),
+            'Œ' | 'œ' => normalized.push_str("oe"),
+            'Ø' | 'ø' => normalized.push('o'),
+            'Ł' | 'ł' => normalized.push('l'),
+            'Đ' | 'đ' => normalized.push('d'),
+            'ı' => normalized.push('i'),
+            c => normalized.extend(c.to_lowercase()),
+        }
+    }
+    normalized
+}
+
+/// Splits text into normalized words using the default [Tokenizer].
+pub fn tokenize(text: &str) -> Vec<String> {
+    Tokenizer::default().tokenize(text)
+}
+
+/// Splits text into normalized words, keeping track of where they are, using the default [Tokenizer].
+pub fn tokens(text: &str) -> Vec<Token> {
+    Tokenizer::default().tokens(text)
+}
+
+/// Languages for which words can be reduced to their stem.
+pub const STEMMED_LANGS: &[&str] = &["en", "fr", "de", "es"];
+
+fn stemmer(lang: &str) -> Option<Stemmer> {
+    let algorithm = match lang {
+        "en" => Algorithm::English,
+        "fr" => Algorithm::French,
+        "de" => Algorithm::German,
+        "es" => Algorithm::Spanish,
+        _ => return None,
+    };
+    Some(Stemmer::create(algorithm))
+}
+
+/// Reduces a normalized word to its stem so that "running" and "run" are the same word.
+/// Words of languages that aren't in [STEMMED_LANGS] are returned unchanged.
+pub fn stem(word: &str, lang: &str) -> String {
+    match stemmer(lang) {
+        Some(stemmer) => stemmer.stem(word).into_owned(),
+        None => word.to_string(),
+    }
+}
+
+/// Returns all the forms a normalized word could have been indexed as, starting with the word itself.
+/// This is used for queries, as their language is unknown.
+pub fn stem_variants(word: &str) -> Vec<String> {
+    let mut variants = vec![word.to_string()];
+    for lang in STEMMED_LANGS {
+        let stemmed = stem(word, lang);
+        if !variants.contains(&stemmed) {
+            variants.push(stemmed);
+        }
+    }
+    variants
+}
+
+/// Number of single character insertions, deletions or substitutions needed to turn a word into another.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + (ca != *cb) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Combining marks used by Latin, Greek and Cyrillic scripts.
+/// Marks of other scripts are often vowels and can't be dropped.
+fn is_diacritic(c: char) -> bool {
+    ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+/// Characters that are written without spaces between words.
+fn is_ideograph(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{309F}' // Hiragana
+        | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{20000}'..='\u{2FA1F}' // CJK Unified Ideographs Extension B to F and supplement
+    )
+}
+
+/// Characters of scripts in which short words are meaningful.
+fn is_cjk(c: char) -> bool {
+    is_ideograph(c) || matches!(c,
+        '\u{30A0}'..='\u{30FF}' // Katakana
+        | '\u{1100}'..='\u{11FF}' // Hangul Jamo
+        | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalization() {
+        assert_eq!(tokenize("Café CAFE café"), vec!["cafe", "cafe", "cafe"]);
+        assert_eq!(tokenize("Straße Œuvre"), vec!["strasse", "oeuvre"]);
+        assert_eq!(tokenize("l'été dernier"), vec!["ete", "dernier"]);
+        assert_eq!(tokenize("Привет мир"), vec!["привет", "мир"]);
+        assert_eq!(tokenize("the QUICK_brown fox, is 42"), vec!["the", "quick_brown", "fox"]);
+    }
+
+    #[test]
+    fn test_cjk() {
+        assert_eq!(tokenize("東京都"), vec!["東京", "京都"]);
+        assert_eq!(tokenize("東 京"), vec!["東", "京"]);
+        assert_eq!(Tokenizer { cjk_bigrams: false }.tokenize("東京都"), vec!["東", "京", "都"]);
+        assert_eq!(tokenize("서울 カタカナ"), vec!["서울", "カタカナ"]);
+    }
+
+    #[test]
+    fn test_stemming() {
+        assert_eq!(stem("running", "en"), stem("run", "en"));
+        assert_eq!(stem("running", "unknown"), "running");
+        assert_eq!(stem_variants("running")[0], "running");
+        assert!(stem_variants("running").contains(&String::from("run")));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("rust", "rust"), 0);
+        assert_eq!(edit_distance("rust", "rusty"), 1);
+        assert_eq!(edit_distance("rust", "bust"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "été"), 3);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let text = "Un café, l’été 東京";
+        for token in tokens(text) {
+            assert_eq!(normalize(&text[token.range.clone()]), token.word);
+        }
+    }
+}
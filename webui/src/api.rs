@@ -161,10 +161,52 @@ pub async fn search(rpc_addr: &str, query: impl AsRef<str>) -> Result<ApiSearchR
     get(format!("{rpc_addr}/search?q={}", url_encode(query.as_ref()))).await
 }
 
-pub async fn fetch_results(rpc_addr: &str, id: u64) -> Result<Vec<(DocumentResult, String)>, ApiError> {
+pub async fn fetch_results(rpc_addr: &str, id: u64) -> Result<Vec<(DocumentResult, String, bool)>, ApiError> {
     get(format!("{rpc_addr}/results?id={id}")).await
 }
 
+/// Receives the events of a search as they happen.
+/// The connection is closed when this is dropped.
+pub struct SearchEventSource {
+    source: EventSource,
+    _onmessage: Closure<dyn FnMut(MessageEvent)>,
+    _onerror: Closure<dyn FnMut(JsValue)>,
+}
+
+impl Drop for SearchEventSource {
+    fn drop(&mut self) {
+        self.source.close();
+    }
+}
+
+pub fn listen_search_events(rpc_addr: &str, id: u64, onevent: Callback<ApiSearchEvent>, onerror: Callback<ApiError>) -> Result<SearchEventSource, ApiError> {
+    let source = EventSource::new(&format!("{rpc_addr}/search-events?id={id}")).map_err(ApiError::Fetch)?;
+
+    let onerror2 = onerror.clone();
+    let onmessage = Closure::wrap(Box::new(move |e: MessageEvent| {
+        let Some(data) = e.data().as_string() else {return};
+        match serde_json::from_str::<ApiSearchEvent>(&data) {
+            Ok(event) => onevent.emit(event),
+            Err(e) => onerror2.emit(ApiError::OutputJson(e)),
+        }
+    }) as Box<dyn FnMut(_)>);
+    for name in ["result", "progress", "search_over"] {
+        source.add_event_listener_with_callback(name, onmessage.as_ref().unchecked_ref()).map_err(ApiError::Fetch)?;
+    }
+
+    // The browser would keep reconnecting, but the search is gone once the connection is lost
+    let onerror = Closure::wrap(Box::new(move |_: JsValue| {
+        onerror.emit(ApiError::Unknown(String::from("Search event stream interrupted")));
+    }) as Box<dyn FnMut(_)>);
+    source.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+
+    Ok(SearchEventSource {
+        source,
+        _onmessage: onmessage,
+        _onerror: onerror,
+    })
+}
+
 pub async fn get_result(rpc_addr: &str, id: u64, cid: &str) -> Result<Option<DocumentResult>, ApiError> {
     get(format!("{rpc_addr}/result?id={id}&cid={cid}")).await
 }
@@ -30,14 +30,18 @@ pub struct ResultsPage {
     update_counter: u32,
     results: RankedResults,
     providers: HashSet<String>,
+    events: Option<SearchEventSource>,
+    search_over: bool,
 }
 
 pub enum ResultsMessage {
     SelectDocumentType(DocumentType),
     SearchSuccess(ApiSearchResponse),
     SearchFailure(ApiError),
-    FetchResultsSuccess { search_id: u64, results: Vec<(DocumentResult, String)> },
+    FetchResultsSuccess { search_id: u64, results: Vec<(DocumentResult, String, bool)> },
     FetchResultsFailure(ApiError),
+    SearchEvent(ApiSearchEvent),
+    SearchEventsFailure(ApiError),
     MaliciousResult(String),
     VerifiedResult(String, Box<DocumentResult>),
 }
@@ -64,6 +68,8 @@ impl Component for ResultsPage {
             update_counter: 0,
             results: RankedResults::new(),
             providers: HashSet::new(),
+            events: None,
+            search_over: false,
         }
     }
     
@@ -74,52 +80,57 @@ impl Component for ResultsPage {
                 true
             }
             ResultsMessage::SearchSuccess(resp) => {
-                let link = ctx.link().clone();
                 self.search_data = Some((resp.id, resp.query));
                 let rpc_addr = ctx.props().conn_status.admarus_addr();
-                spawn_local(async move {
-                    sleep(Duration::from_millis(100)).await;
-                    match fetch_results(rpc_addr, resp.id).await {
-                        Ok(results) => link.send_message(ResultsMessage::FetchResultsSuccess { search_id: resp.id, results }),
-                        Err(e) => link.send_message(ResultsMessage::FetchResultsFailure(e)),
-                    }
-                });
+                let onevent = ctx.link().callback(ResultsMessage::SearchEvent);
+                let onerror = ctx.link().callback(ResultsMessage::SearchEventsFailure);
+                match listen_search_events(rpc_addr, resp.id, onevent, onerror) {
+                    Ok(events) => self.events = Some(events),
+                    Err(_) => self.poll_results(ctx, resp.id, Duration::from_millis(100)),
+                }
                 false
             }
             ResultsMessage::FetchResultsSuccess { search_id: results_search_id, results } => {
-                let Some((search_id, query)) = &self.search_data else { return false };
+                let Some((search_id, _)) = &self.search_data else { return false };
                 let search_id = *search_id;
                 let update_counter = self.update_counter;
                 if results_search_id != search_id { return false };
 
-                // Insert results and rank them
                 self.update_counter += 1;
-                let new_results = !results.is_empty();
-                for (result, provider) in results {
-                    self.results.insert(result, provider.clone(), query);
-                    self.providers.insert(provider);
-                }
-                self.results.rerank();
-                if new_results {
-                    self.results.rerank();
-                }
+                self.insert_results(results);
 
-                let link = ctx.link().clone();
-                let rpc_addr = ctx.props().conn_status.admarus_addr();
-                spawn_local(async move {
-                    match update_counter {
-                        0..=10 => sleep(Duration::from_millis(100)).await,
-                        11..=20 => sleep(Duration::from_millis(300)).await,
-                        _ => sleep(Duration::from_secs(1)).await,
-                    }
-                    match fetch_results(rpc_addr, search_id).await {
-                        Ok(results) => link.send_message(ResultsMessage::FetchResultsSuccess { search_id, results }),
-                        Err(e) => link.send_message(ResultsMessage::FetchResultsFailure(e)),
-                    }
-                });
+                let delay = match update_counter {
+                    0..=10 => Duration::from_millis(100),
+                    11..=20 => Duration::from_millis(300),
+                    _ => Duration::from_secs(1),
+                };
+                self.poll_results(ctx, search_id, delay);
 
                 true
             }
+            ResultsMessage::SearchEvent(event) => match event {
+                ApiSearchEvent::Result { result, provider, verified } => {
+                    self.insert_results(vec![(result, provider, verified)]);
+                    true
+                },
+                ApiSearchEvent::Progress { .. } => {
+                    self.update_counter += 1;
+                    self.update_counter == 10
+                },
+                ApiSearchEvent::SearchOver => {
+                    self.search_over = true;
+                    self.events = None;
+                    true
+                },
+            },
+            ResultsMessage::SearchEventsFailure(_) => {
+                // Older daemons don't stream events, so results are polled instead
+                self.events = None;
+                if let (false, Some((search_id, _))) = (self.search_over, &self.search_data) {
+                    self.poll_results(ctx, *search_id, Duration::from_millis(100));
+                }
+                false
+            }
             ResultsMessage::MaliciousResult(cid) => {
                 self.results.malicious_result(cid);
                 true
@@ -175,7 +186,7 @@ impl Component for ResultsPage {
         };
 
         // No-results message
-        let no_results = results.is_empty() && self.search_error.is_none() && self.update_counter >= 10;
+        let no_results = results.is_empty() && self.search_error.is_none() && (self.update_counter >= 10 || self.search_over);
         let many_keywords = ctx.props().query.split_whitespace().count() >= 3;
         let lucky_query = get_lucky_query(search_id);
         let onclick_lucky = ctx.props().app_link.callback(move |_| AppMsg::ChangePage(Page::lucky(search_id)));
@@ -215,3 +226,37 @@ impl Component for ResultsPage {
         )
     }
 }
+
+impl ResultsPage {
+    fn insert_results(&mut self, results: Vec<(DocumentResult, String, bool)>) {
+        let Some((_, query)) = &self.search_data else { return };
+
+        // Insert results and rank them
+        let new_results = !results.is_empty();
+        for (result, provider, verified) in results {
+            // The daemon generated verified results itself, so they can be trusted
+            let trusted = verified.then(|| result.clone());
+            self.results.insert(result, provider.clone(), query);
+            if let Some(trusted) = trusted {
+                self.results.verified_result(trusted.cid.clone(), trusted);
+            }
+            self.providers.insert(provider);
+        }
+        self.results.rerank();
+        if new_results {
+            self.results.rerank();
+        }
+    }
+
+    fn poll_results(&self, ctx: &Context<Self>, search_id: u64, delay: Duration) {
+        let link = ctx.link().clone();
+        let rpc_addr = ctx.props().conn_status.admarus_addr();
+        spawn_local(async move {
+            sleep(delay).await;
+            match fetch_results(rpc_addr, search_id).await {
+                Ok(results) => link.send_message(ResultsMessage::FetchResultsSuccess { search_id, results }),
+                Err(e) => link.send_message(ResultsMessage::FetchResultsFailure(e)),
+            }
+        });
+    }
+}
@@ -117,7 +117,7 @@ impl DocumentResult {
             (_, Some(h1)) => h1,
             (None, None) => return false,
         };
-        let title_words = title.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| w.len() >= 3).map(|w| w.to_lowercase()).collect::<Vec<_>>();
+        let title_words = tokenizer::tokenize(title);
 
         fn words_match_query(comp: &QueryComp, words: &[String]) -> bool {
             match comp {
@@ -136,6 +136,9 @@ impl DocumentResult {
                     }
                     false
                 }
+                QueryComp::Phrase(inner) => inner.iter().all(|inner| words_match_query(inner, words)),
+                QueryComp::Prefix { prefix, .. } => words.iter().any(|w| TermMatcher::Prefix(prefix).matches(w)),
+                QueryComp::Fuzzy { word, distance, .. } => words.iter().any(|w| TermMatcher::Fuzzy(word, *distance).matches(w)),
             }
         }
 
@@ -185,25 +188,25 @@ impl DocumentResult {
 
     pub fn view_desc(&self, query: &Query) -> VList {
         // TODO: this is a copy of daemon code
-        fn extract_score(extract: &str, query: &[&String]) -> usize {
+        fn extract_score(extract: &str, query: &[TermMatcher]) -> usize {
             let mut score = 0;
-            let mut extract_words = extract.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| w.len() >= 3).map(|w| w.to_lowercase()).collect::<Vec<_>>();
+            let mut extract_words = tokenizer::tokenize(extract);
             if extract_words.is_empty() {
                 return 0;
             }
             let first_word = extract_words.remove(0);
-            if query.contains(&&first_word) {
+            if query.iter().any(|m| m.matches(&first_word)) {
                 score += 4;
             }
-            for word in query {
-                if extract_words.contains(word) {
+            for matcher in query {
+                if extract_words.iter().any(|w| matcher.matches(w)) {
                     score += 1;
                 }
             }
             score
         }
 
-        let query_terms = query.positive_terms();
+        let query_terms = query.positive_matchers();
         let desc = match (&self.description, &self.extract) {
             (Some(desc), Some(extract)) => {
                 if extract_score(desc, query_terms.as_slice()) >= extract_score(extract, query_terms.as_slice()) {
@@ -216,27 +219,26 @@ impl DocumentResult {
             (None, Some(extract)) => extract,
             (None, None) => return VList::new(),
         };
-        let mut i = 0;
         let mut added = 0;
         let mut vlist = VList::new();
-        for part in desc.split_inclusive(|c: char| !c.is_ascii_alphanumeric()) {
-            let part_len = part.len();
-            let word = part.trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
-            if word.len() >= 3 && query_terms.contains(&&word.to_lowercase()) {
-                if i - added > 0 {
-                    let unbolded_text = desc[added..i].to_string();
-                    vlist.add_child(VText::new(unbolded_text).into());
-                }
-                let bolded_text = desc[i..i + word.len()].to_string();
-                let mut b_el = VTag::new("b");
-                b_el.add_child(VText::new(bolded_text).into());
-                vlist.add_child(b_el.into());
-                added = i + word.len();
+        for token in tokenizer::tokens(desc) {
+            // CJK bigrams overlap
+            let start = std::cmp::max(token.range.start, added);
+            if start >= token.range.end || !query_terms.iter().any(|m| m.matches(&token.word)) {
+                continue;
+            }
+            if start > added {
+                let unbolded_text = desc[added..start].to_string();
+                vlist.add_child(VText::new(unbolded_text).into());
             }
-            i += part_len;
+            let bolded_text = desc[start..token.range.end].to_string();
+            let mut b_el = VTag::new("b");
+            b_el.add_child(VText::new(bolded_text).into());
+            vlist.add_child(b_el.into());
+            added = token.range.end;
         }
-        if i - added > 0 {
-            let unbolded_text = desc[added..i].to_string();
+        if desc.len() > added {
+            let unbolded_text = desc[added..].to_string();
             vlist.add_child(VText::new(unbolded_text).into());
         }
 
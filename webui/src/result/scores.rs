@@ -18,6 +18,8 @@ impl QueryComp {
                 mapped_counts.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
                 mapped_counts.into_iter().take(*n).sum::<f64>() / *n as f64
             }
+            QueryComp::Phrase(words) => words.iter().map(|c| c.map_counts(counts)).sum::<f64>() / words.len() as f64,
+            QueryComp::Prefix { prefix: w, .. } | QueryComp::Fuzzy { word: w, .. } => counts.get(w).copied().unwrap_or(0.0),
         }
     }
 }
@@ -35,12 +37,7 @@ impl DocumentResult {
         // Title is counted separately as it is not part of the document body
         let title_words  = self.title
             .as_ref()
-            .map(|t| t
-                .to_lowercase()
-                .split(|c: char| !c.is_ascii_alphanumeric())
-                .filter(|w| w.len() >= 3)
-                .map(|w| w.to_string())
-            .collect::<Vec<_>>())
+            .map(|t| tokenizer::tokenize(t))
             .unwrap_or_default();
         let title_word_count = title_words.len();
         let title_word_count = title_word_count as f64 * 12.0;
@@ -66,12 +63,7 @@ impl DocumentResult {
                     None => {
                         let Some(title_words_some) = self.title
                             .as_ref()
-                            .map(|t| t
-                                .to_lowercase()
-                                .split(|c: char| !c.is_ascii_alphanumeric())
-                                .filter(|w| w.len() >= 3)
-                                .map(|w| w.to_string())
-                            .collect::<Vec<_>>())
+                            .map(|t| tokenizer::tokenize(t))
                             else {continue};
                         title_words_some
                     }
@@ -117,18 +109,16 @@ impl DocumentResult {
             Some(common_words) => common_words,
             None => {
                 let mut words = Vec::new();
-                let description_lowercase = self.description.as_ref().map(|d| d.to_lowercase());
-                let extract_lowercase = self.extract.as_ref().map(|e| e.to_lowercase());
-                if let Some(description) = &description_lowercase {
-                    words.extend(description.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| w.len() >= 3));
+                if let Some(description) = &self.description {
+                    words.extend(tokenizer::tokenize(description));
                 }
-                if let Some(extract) = &extract_lowercase {
-                    words.extend(extract.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| w.len() >= 3));
+                if let Some(extract) = &self.extract {
+                    words.extend(tokenizer::tokenize(extract));
                 }
 
                 let words_bytes = words.iter().map(|w| w.len()).sum::<usize>();
                 let lang_words = requested_lang.common_words();
-                let common_words_bytes = words.iter().filter(|w| lang_words.sorted_contains(w)).map(|w| w.len()).sum::<usize>();
+                let common_words_bytes = words.iter().filter(|w| lang_words.sorted_contains(w.as_str())).map(|w| w.len()).sum::<usize>();
                 common_words_bytes as f64 / words_bytes as f64
             },
         };
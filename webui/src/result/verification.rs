@@ -0,0 +1 @@
+../../../daemon/src/result/verification.rs
\ No newline at end of file